@@ -8,20 +8,71 @@ fn main() {
             ci_tag = s.trim().to_string();
         }
     } else if let Ok(output) = std::process::Command::new("git")
-        .args(&[
-            "-c",
-            "core.abbrev=8",
-            "show",
-            "-s",
-            "--format=%cd-%h",
-            "--date=format:%Y.%m.%d",
-        ])
+        .args(["describe", "--tags", "--abbrev=0", "--dirty"])
         .output()
     {
-        let info = String::from_utf8_lossy(&output.stdout);
-        ci_tag = info.trim().to_string();
+        // Only the tag is used here; the commit hash is appended
+        // separately by pview_version.
+        // Leave the tag empty when there are no tags to describe,
+        // or when we are not building from a git checkout
+        if output.status.success() {
+            let info = String::from_utf8_lossy(&output.stdout);
+            ci_tag = info.trim().to_string();
+        }
     }
 
+    let git_hash = git(&["rev-parse", "--short=8", "HEAD"]).unwrap_or_default();
+
     println!("cargo:rerun-if-changed=.tag");
+    // Pick up new commits and branch switches, so that the
+    // embedded hash doesn't go stale
+    if let Some(git_dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={git_dir}/HEAD");
+        if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={git_dir}/{head_ref}");
+        }
+        println!("cargo:rerun-if-changed={git_dir}/packed-refs");
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
     println!("cargo:rustc-env=PVIEW_CI_TAG={ci_tag}");
+    println!("cargo:rustc-env=PVIEW_GIT_HASH={git_hash}");
+    println!("cargo:rustc-env=PVIEW_BUILD_DATE={}", build_date());
+}
+
+/// Run git with `args`, returning its trimmed output if it succeeded
+fn git(args: &[&str]) -> Option<String> {
+    std::process::Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Returns the build date as YYYY-MM-DD (UTC), honoring
+/// SOURCE_DATE_EPOCH for reproducible builds.
+fn build_date() -> String {
+    let secs = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0)
+        });
+
+    // Convert days since the unix epoch to a civil date; see
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let z = secs.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{year:04}-{month:02}-{day:02}")
 }
@@ -24,9 +24,21 @@ impl std::fmt::Display for Base64Name {
 }
 
 impl Base64Name {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self(name.into())
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    pub fn contains(&self, pat: &str) -> bool {
+        self.0.contains(pat)
+    }
+
+    pub fn starts_with(&self, pat: &str) -> bool {
+        self.0.starts_with(pat)
+    }
 }
 
 impl std::ops::Deref for Base64Name {
@@ -62,7 +74,7 @@ impl Serialize for Base64Name {
     where
         S: Serializer,
     {
-        let encoded = data_encoding::BASE64.encode(&self.0.as_bytes());
+        let encoded = data_encoding::BASE64.encode(self.0.as_bytes());
         encoded.serialize(serializer)
     }
 }
@@ -88,6 +100,17 @@ pub enum RoomType {
     DefaultRoom = 2,
 }
 
+impl RoomType {
+    /// A short human readable explanation of the room type
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Self::Regular => "Regular",
+            Self::Repeater => "Repeater (holds repeaters, not shades)",
+            Self::DefaultRoom => "Default (shades not assigned to a room)",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -124,6 +147,13 @@ pub struct ShadeData {
     pub shade_type: ShadeType,
     #[serde(default)]
     pub timed_out: bool,
+    /// The position that the shade moves to when it is sent the
+    /// Heart motion. Not all hub firmware reports this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favorite: Option<ShadePosition>,
+    /// Only reported by some firmware versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
 }
 
 impl ShadeData {
@@ -159,6 +189,87 @@ impl ShadeData {
     pub fn pos2_percent(&self) -> Option<u8> {
         self.positions.as_ref().and_then(|p| p.pos2_percent())
     }
+
+    /// Returns true if the vanes of this shade can be tilted
+    pub fn can_tilt(&self) -> bool {
+        self.capabilities
+            .flags()
+            .intersects(ShadeCapabilityFlags::TILT_ANYWHERE | ShadeCapabilityFlags::TILT_ON_CLOSED)
+    }
+
+    /// Returns the vane tilt as a percentage of the available range,
+    /// if the hub reports the vanes as tilted
+    pub fn tilt_percent(&self) -> Option<u8> {
+        let position = self.positions.as_ref()?;
+        if !matches!(position.pos_kind_1, PositionKind::VaneTilt) {
+            return None;
+        }
+        let max = self.capabilities.flags().max_tilt() as u32;
+        Some(((100 * (position.position_1 as u32).min(max) + max / 2) / max) as u8)
+    }
+
+    /// Returns the position that will tilt the vanes to the
+    /// specified percentage of the available range
+    pub fn tilt_position(&self, pct: u8) -> ShadePosition {
+        let max = self.capabilities.flags().max_tilt() as u32;
+        ShadePosition {
+            pos_kind_1: PositionKind::VaneTilt,
+            pos_kind_2: None,
+            position_1: ((max * pct.min(100) as u32 + 50) / 100) as u16,
+            position_2: None,
+        }
+    }
+
+    /// Returns the list of reasons that this shade may need attention.
+    /// `battery_threshold` is the percentage below which the battery
+    /// is considered to be too low, even if the hub doesn't yet consider
+    /// it to be low.
+    pub fn problems(&self, battery_threshold: u8) -> Vec<ShadeProblem> {
+        let mut problems = vec![];
+        if self.timed_out {
+            problems.push(ShadeProblem::TimedOut);
+        }
+        if self.battery_status == BatteryStatus::Low {
+            problems.push(ShadeProblem::LowBattery);
+        } else if self.battery_status != BatteryStatus::PluggedIn {
+            if let Some(percent) = self.battery_percent() {
+                if percent < battery_threshold {
+                    problems.push(ShadeProblem::BatteryBelowThreshold(percent));
+                }
+            }
+        }
+        if self.positions.is_none() {
+            problems.push(ShadeProblem::NoPosition);
+        }
+        problems
+    }
+
+    pub fn is_healthy(&self, battery_threshold: u8) -> bool {
+        self.problems(battery_threshold).is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShadeProblem {
+    /// The hub timed out while communicating with the shade
+    TimedOut,
+    /// The hub reports that the battery is low
+    LowBattery,
+    /// The battery level is below the requested threshold
+    BatteryBelowThreshold(u8),
+    /// The hub doesn't know the position of the shade
+    NoPosition,
+}
+
+impl std::fmt::Display for ShadeProblem {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::TimedOut => write!(fmt, "timed out"),
+            Self::LowBattery => write!(fmt, "battery low"),
+            Self::BatteryBelowThreshold(pct) => write!(fmt, "battery {pct}%"),
+            Self::NoPosition => write!(fmt, "no position"),
+        }
+    }
 }
 
 #[derive(Serialize_repr, Deserialize_repr, Debug, PartialEq, Eq)]
@@ -181,6 +292,22 @@ pub struct ShadeFirmware {
     pub sub_revision: i32,
 }
 
+/// Formats a firmware version in the form used by the PowerView app
+fn fmt_version(
+    fmt: &mut std::fmt::Formatter,
+    revision: i32,
+    sub_revision: i32,
+    build: i32,
+) -> std::fmt::Result {
+    write!(fmt, "{revision}.{sub_revision}.{build}")
+}
+
+impl std::fmt::Display for ShadeFirmware {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_version(fmt, self.revision, self.sub_revision, self.build)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -206,12 +333,47 @@ impl ShadePosition {
         }
     }
 
+    // Both conversions round to the nearest value, rather than
+    // truncating, so that converting a percentage to a position
+    // and back yields the original percentage.
     pub fn pos_to_percent(pos: u16) -> u8 {
-        (100u32 * pos as u32 / u16::max_value() as u32) as u8
+        let max = u16::MAX as u32;
+        ((100u32 * pos as u32 + max / 2) / max) as u8
     }
 
     pub fn percent_to_pos(pct: u8) -> u16 {
-        ((u16::max_value() as u32) * (pct as u32) / 100u32) as u16
+        (((u16::MAX as u32) * (pct.min(100) as u32) + 50) / 100u32) as u16
+    }
+
+    /// Move one rail of an existing position to `pct` percent,
+    /// leaving the other rail where it is.
+    pub fn set_rail_percent(&mut self, is_primary: bool, pct: u8) {
+        let absolute = Self::percent_to_pos(pct);
+        if is_primary {
+            self.position_1 = absolute;
+        } else {
+            self.position_2.replace(absolute);
+        }
+    }
+
+    /// Construct a position from percentages for each rail.
+    /// `pos2_pct` is only used when `pos_kind_2` is specified.
+    pub fn from_percent_pair(
+        pos_kind_1: PositionKind,
+        pos1_pct: u8,
+        pos_kind_2: Option<PositionKind>,
+        pos2_pct: Option<u8>,
+    ) -> Self {
+        let position_2 = match pos_kind_2 {
+            Some(_) => pos2_pct.map(Self::percent_to_pos),
+            None => None,
+        };
+        Self {
+            pos_kind_1,
+            pos_kind_2: position_2.and(pos_kind_2),
+            position_1: Self::percent_to_pos(pos1_pct),
+            position_2,
+        }
     }
 
     pub fn pos1_percent(&self) -> u8 {
@@ -249,7 +411,7 @@ pub enum PositionKind {
     Error = 4,
 }
 
-#[derive(Serialize_repr, Deserialize_repr, Debug)]
+#[derive(Serialize_repr, Deserialize_repr, Debug, Clone, Copy)]
 #[repr(i32)]
 pub enum ShadeType {
     Roller = 1,
@@ -350,6 +512,19 @@ bitflags::bitflags! {
     }
 }
 
+impl ShadeCapabilityFlags {
+    /// The raw position value corresponding to fully tilted vanes.
+    /// Vanes that tilt through 90 degrees use half of the range
+    /// used by those that tilt through 180 degrees.
+    pub fn max_tilt(self) -> u16 {
+        if self.contains(Self::TILT_180) {
+            u16::MAX
+        } else {
+            u16::MAX / 2
+        }
+    }
+}
+
 #[derive(Serialize_repr, Deserialize_repr, Debug, Copy, Clone)]
 #[repr(i32)]
 pub enum ShadeBatteryKind {
@@ -358,6 +533,46 @@ pub enum ShadeBatteryKind {
     RechargeableBattery = 3,
 }
 
+impl ShadeBatteryKind {
+    pub const HARD_WIRED_LABEL: &'static str = "Hard Wired";
+    pub const BATTERY_LABEL: &'static str = "Battery";
+    pub const RECHARGEABLE_LABEL: &'static str = "Rechargeable Battery";
+
+    /// The human readable name of this kind, as shown in Home Assistant
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::HardWiredPowerSupply => Self::HARD_WIRED_LABEL,
+            Self::BatteryWand => Self::BATTERY_LABEL,
+            Self::RechargeableBattery => Self::RECHARGEABLE_LABEL,
+        }
+    }
+}
+
+/// Accepts short names on the command line, as well as
+/// the labels that are used in Home Assistant
+impl clap::ValueEnum for ShadeBatteryKind {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::HardWiredPowerSupply,
+            Self::BatteryWand,
+            Self::RechargeableBattery,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let name = match self {
+            Self::HardWiredPowerSupply => "hard-wired",
+            Self::BatteryWand => "battery",
+            Self::RechargeableBattery => "rechargeable",
+        };
+        Some(
+            clap::builder::PossibleValue::new(name)
+                .alias(self.label())
+                .help(self.label()),
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -376,6 +591,12 @@ pub struct Motor {
     pub build: i32,
 }
 
+impl std::fmt::Display for Motor {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_version(fmt, self.revision, self.sub_revision, self.build)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, clap::ValueEnum)]
 #[serde(rename_all = "camelCase")]
 pub enum ShadeUpdateMotion {
@@ -403,6 +624,231 @@ pub struct Scene {
     pub hk_assist: bool,
 }
 
+/// An automation that activates a scene, or scene collection,
+/// at a particular time of day
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Automation {
+    pub id: i32,
+    #[serde(default)]
+    pub name: Option<Base64Name>,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub event: AutomationEvent,
+    #[serde(flatten)]
+    pub action: AutomationAction,
+}
+
+/// What triggers an automation
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationEvent {
+    /// 0 for a time of day, 1 for sunrise and 2 for sunset
+    pub event_type: i32,
+    /// For sunrise and sunset events, the hour and minute
+    /// are an offset relative to the event
+    pub hour: i32,
+    pub minute: i32,
+}
+
+impl std::fmt::Display for AutomationEvent {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let offset = self.hour * 60 + self.minute;
+        match self.event_type {
+            0 => write!(fmt, "Time {:02}:{:02}", self.hour, self.minute),
+            1 | 2 => {
+                let label = if self.event_type == 1 {
+                    "Sunrise"
+                } else {
+                    "Sunset"
+                };
+                if offset == 0 {
+                    write!(fmt, "{label}")
+                } else {
+                    write!(fmt, "{label} {offset:+}m")
+                }
+            }
+            other => write!(fmt, "Unknown ({other})"),
+        }
+    }
+}
+
+/// What an automation does when it is triggered
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationAction {
+    #[serde(default)]
+    pub scene_id: Option<i32>,
+    #[serde(default)]
+    pub scene_collection_id: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationsResponse {
+    pub automation_data: Vec<Automation>,
+}
+
+/// A scheduled event, which activates a scene, or scene collection,
+/// at a time of day, or relative to sunrise or sunset, on particular
+/// days of the week
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledEvent {
+    pub id: i32,
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub event: AutomationEvent,
+    #[serde(flatten)]
+    pub action: AutomationAction,
+    #[serde(flatten)]
+    pub days: ScheduledEventDays,
+}
+
+/// The days of the week on which a scheduled event runs
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledEventDays {
+    #[serde(default)]
+    pub day_monday: bool,
+    #[serde(default)]
+    pub day_tuesday: bool,
+    #[serde(default)]
+    pub day_wednesday: bool,
+    #[serde(default)]
+    pub day_thursday: bool,
+    #[serde(default)]
+    pub day_friday: bool,
+    #[serde(default)]
+    pub day_saturday: bool,
+    #[serde(default)]
+    pub day_sunday: bool,
+}
+
+impl ScheduledEventDays {
+    /// Returns the enabled days, starting with Monday
+    pub fn days(&self) -> Vec<&'static str> {
+        [
+            (self.day_monday, "Mon"),
+            (self.day_tuesday, "Tue"),
+            (self.day_wednesday, "Wed"),
+            (self.day_thursday, "Thu"),
+            (self.day_friday, "Fri"),
+            (self.day_saturday, "Sat"),
+            (self.day_sunday, "Sun"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+}
+
+impl std::fmt::Display for ScheduledEventDays {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let days = self.days();
+        match days.as_slice() {
+            [] => write!(fmt, "Never"),
+            ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"] => write!(fmt, "Every day"),
+            ["Mon", "Tue", "Wed", "Thu", "Fri"] => write!(fmt, "Weekdays"),
+            ["Sat", "Sun"] => write!(fmt, "Weekends"),
+            days => write!(fmt, "{}", days.join(", ")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledEventsResponse {
+    pub scheduled_event_data: Vec<ScheduledEvent>,
+}
+
+/// A device that extends the range of the hub's radio network.
+/// Newer firmware may add fields, so unknown fields are ignored.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Repeater {
+    pub id: i32,
+    pub name: Option<Base64Name>,
+    #[serde(default)]
+    pub room_id: Option<i32>,
+    #[serde(default)]
+    pub color: Option<RepeaterColor>,
+    #[serde(default)]
+    pub firmware: Option<RepeaterFirmware>,
+}
+
+/// The firmware version of a Repeater; like `ShadeFirmware`,
+/// but tolerating unknown fields
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepeaterFirmware {
+    pub build: i32,
+    pub revision: i32,
+    pub sub_revision: i32,
+}
+
+impl std::fmt::Display for RepeaterFirmware {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_version(fmt, self.revision, self.sub_revision, self.build)
+    }
+}
+
+/// The LED color of a Repeater; like `Color`,
+/// but tolerating unknown fields
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RepeaterColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub brightness: u8,
+}
+
+impl std::fmt::Display for RepeaterColor {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_color(fmt, self.red, self.green, self.blue, self.brightness)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RepeatersResponse {
+    pub repeater_data: Vec<Repeater>,
+}
+
+/// A named group of scenes that can be activated together
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneCollection {
+    pub color_id: i32,
+    pub icon_id: i32,
+    pub id: i32,
+    pub name: Base64Name,
+    pub order: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneCollectionsResponse {
+    pub scene_collection_data: Vec<SceneCollection>,
+    pub scene_collection_ids: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneCollectionMember {
+    pub id: i32,
+    pub scene_collection_id: i32,
+    pub scene_id: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneCollectionMembersResponse {
+    pub scene_collection_member_data: Vec<SceneCollectionMember>,
+    pub scene_collection_member_ids: Vec<u32>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -479,6 +925,16 @@ pub struct UserData {
     pub remote_connect_enabled: bool,
 }
 
+impl std::fmt::Display for UserData {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            fmt,
+            "{} Hub: {} [{}] SN={}",
+            self.brand, self.hub_name, self.ip, self.serial_number
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -497,6 +953,12 @@ pub struct MainProcessorFirmware {
     pub build: i32,
 }
 
+impl std::fmt::Display for MainProcessorFirmware {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_version(fmt, self.revision, self.sub_revision, self.build)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -507,6 +969,23 @@ pub struct Color {
     pub brightness: u8,
 }
 
+/// Formats an LED color as a hex triplet and brightness percentage
+fn fmt_color(
+    fmt: &mut std::fmt::Formatter,
+    red: u8,
+    green: u8,
+    blue: u8,
+    brightness: u8,
+) -> std::fmt::Result {
+    write!(fmt, "#{red:02x}{green:02x}{blue:02x} {brightness}%")
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fmt_color(fmt, self.red, self.green, self.blue, self.brightness)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -535,6 +1014,50 @@ pub struct HomeAutomationPostBackData {
     pub stopped_position: Option<u8>,
 }
 
+/// The API features supported by a hub
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HubCapabilities {
+    #[serde(default)]
+    pub scenes: bool,
+    #[serde(default)]
+    pub scene_collections: bool,
+    #[serde(default)]
+    pub schedules: bool,
+    #[serde(default)]
+    pub automations: bool,
+    #[serde(default)]
+    pub repeaters: bool,
+}
+
+impl HubCapabilities {
+    /// The capabilities of a Gen2 hub, which has no api to report them
+    pub fn gen2() -> Self {
+        Self {
+            scenes: true,
+            scene_collections: true,
+            schedules: true,
+            automations: true,
+            repeaters: true,
+        }
+    }
+}
+
+/// The home automation hook configuration, as reported by the hub
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HomeAutomationHook {
+    pub enabled: bool,
+    #[serde(default)]
+    pub post_back_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct HomeAutomationHookResponse {
+    pub homeautomation: HomeAutomationHook,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, clap::ValueEnum)]
 #[serde(rename_all = "camelCase")]
 pub enum HomeAutomationService {
@@ -561,3 +1084,81 @@ pub enum HomeAutomationRecordType {
     HasClosed,
     Stops,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_percent_pair() {
+        for (pct, pos) in [(0, 0), (50, 32768), (100, u16::MAX)] {
+            let position =
+                ShadePosition::from_percent_pair(PositionKind::PrimaryRail, pct, None, Some(pct));
+            assert_eq!(position.position_1, pos, "{pct}%");
+            assert_eq!(position.position_2, None, "{pct}%");
+            assert!(position.pos_kind_2.is_none());
+
+            let position = ShadePosition::from_percent_pair(
+                PositionKind::PrimaryRail,
+                pct,
+                Some(PositionKind::SecondaryRail),
+                Some(pct),
+            );
+            assert_eq!(position.position_1, pos, "{pct}%");
+            assert_eq!(position.position_2, Some(pos), "{pct}%");
+            assert!(matches!(
+                position.pos_kind_2,
+                Some(PositionKind::SecondaryRail)
+            ));
+        }
+    }
+
+    #[test]
+    fn percent_round_trip() {
+        for pct in 0..=100 {
+            let pos = ShadePosition::percent_to_pos(pct);
+            assert_eq!(ShadePosition::pos_to_percent(pos), pct, "pos={pos}");
+        }
+    }
+
+    #[test]
+    fn tilt_round_trip() {
+        for capabilities in [
+            ShadeCapabilities::BottomUpTilt90,
+            ShadeCapabilities::BottomUpTilt180,
+        ] {
+            let mut shade: ShadeData = serde_json::from_value(serde_json::json!({
+                "batteryStatus": 3,
+                "batteryStrength": 180,
+                "capabilities": capabilities as i32,
+                "batteryKind": 2,
+                "smartPowerSupply": {"status": 0, "id": 0, "port": 0},
+                "groupId": 1,
+                "id": 1,
+                "type": 1,
+            }))
+            .unwrap();
+            for pct in 0..=100 {
+                shade.positions.replace(shade.tilt_position(pct));
+                assert_eq!(shade.tilt_percent(), Some(pct), "{capabilities:?} {pct}%");
+            }
+        }
+    }
+
+    #[test]
+    fn set_rail_percent() {
+        let mut position = ShadePosition::from_percent_pair(
+            PositionKind::PrimaryRail,
+            0,
+            Some(PositionKind::SecondaryRail),
+            Some(100),
+        );
+        position.set_rail_percent(true, 50);
+        assert_eq!(position.position_1, 32768);
+        assert_eq!(position.position_2, Some(u16::MAX));
+
+        position.set_rail_percent(false, 0);
+        assert_eq!(position.position_1, 32768);
+        assert_eq!(position.position_2, Some(0));
+    }
+}
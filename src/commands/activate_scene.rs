@@ -1,7 +1,7 @@
 /// Activate a scene
 #[derive(clap::Parser, Debug)]
 pub struct ActivateSceneCommand {
-    /// The name or id of the shade to inspect.
+    /// The name or id of the scene to activate.
     /// Names will be compared ignoring case.
     name: String,
 }
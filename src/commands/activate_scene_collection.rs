@@ -0,0 +1,22 @@
+/// Activate a scene collection
+#[derive(clap::Parser, Debug)]
+pub struct ActivateSceneCollectionCommand {
+    /// The name or id of the scene collection to activate.
+    /// Names will be compared ignoring case.
+    name: String,
+}
+
+impl ActivateSceneCollectionCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let collection = hub.scene_collection_by_name(&self.name).await?;
+        hub.activate_scene_collection(collection.id).await?;
+
+        println!(
+            "Activated scene collection {} {}",
+            collection.id, collection.name
+        );
+        Ok(())
+    }
+}
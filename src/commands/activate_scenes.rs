@@ -0,0 +1,28 @@
+/// Activate several scenes at the same time
+#[derive(clap::Parser, Debug)]
+pub struct ActivateScenesCommand {
+    /// The names or ids of the scenes to activate.
+    /// Names will be compared ignoring case.
+    #[arg(required = true)]
+    names: Vec<String>,
+}
+
+impl ActivateScenesCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        // Resolve all of the names up front, so that a typo
+        // doesn't result in only some of the scenes activating
+        let mut scenes = vec![];
+        for name in &self.names {
+            scenes.push(hub.scene_by_name(name).await?);
+        }
+        let scene_ids: Vec<i32> = scenes.iter().map(|scene| scene.id).collect();
+
+        let results = hub.activate_scenes_parallel(&scene_ids).await?;
+        for (scene, (_, shades)) in scenes.iter().zip(results) {
+            println!("{} {}: {shades:?}", scene.id, scene.name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use crate::api_types::{PositionKind, ShadePosition};
+
+#[derive(clap::Args, Debug)]
+#[group(required = true, multiple = true)]
+struct MemberPosition {
+    /// The position that the shade should take when the
+    /// scene is activated
+    #[arg(long)]
+    percent: Option<u8>,
+
+    /// The position that the secondary rail of the shade should
+    /// take when the scene is activated
+    #[arg(long)]
+    secondary_percent: Option<u8>,
+
+    /// Use the current position of the shade
+    #[arg(long, conflicts_with_all = ["percent", "secondary_percent"])]
+    use_current: bool,
+}
+
+/// Add a shade to a scene, at the specified position.
+/// If the shade is already a member of the scene, its position
+/// in the scene is updated.
+#[derive(clap::Parser, Debug)]
+pub struct AddShadeToSceneCommand {
+    /// The name or id of the scene.
+    /// Names will be compared ignoring case.
+    scene: String,
+
+    /// The name or id of the shade to add to the scene.
+    /// Names will be compared ignoring case.
+    shade: String,
+
+    #[command(flatten)]
+    position: MemberPosition,
+}
+
+impl AddShadeToSceneCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let scene = hub.scene_by_name(&self.scene).await?;
+        let shade = hub.shade_by_id_or_name(&self.shade).await?;
+
+        let existing = hub
+            .list_scene_members()
+            .await?
+            .remove(&scene.id)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|member| member.shade_id == shade.id);
+
+        let mut position = if self.position.use_current {
+            hub.shade_by_id(shade.id).await?.positions
+        } else {
+            existing
+                .as_ref()
+                .map(|member| member.positions.clone())
+                .or_else(|| shade.positions.clone())
+        }
+        .ok_or_else(|| anyhow::anyhow!("shade has no existing position information! {shade:#?}"))?;
+
+        if let Some(percent) = self.position.percent {
+            position.set_rail_percent(shade.is_primary(), percent);
+        }
+        if let Some(percent) = self.position.secondary_percent {
+            position
+                .position_2
+                .replace(ShadePosition::percent_to_pos(percent));
+            if position.pos_kind_2.is_none() {
+                position.pos_kind_2.replace(PositionKind::SecondaryRail);
+            }
+        }
+
+        // There is no way to modify a member in place, so replace it
+        if let Some(existing) = &existing {
+            hub.delete_scene_member(existing.id).await?;
+        }
+        let member = match hub.add_scene_member(scene.id, shade.id, position).await {
+            Ok(member) => member,
+            Err(err) => {
+                // Put the shade back where it was, rather than
+                // silently dropping it from the scene
+                if let Some(existing) = existing {
+                    if let Err(restore_err) = hub
+                        .add_scene_member(scene.id, shade.id, existing.positions)
+                        .await
+                    {
+                        log::error!(
+                            "failed to restore {} to scene {}: {restore_err:#}",
+                            shade.name(),
+                            scene.name
+                        );
+                    }
+                }
+                return Err(err);
+            }
+        };
+
+        println!("{member:#?}");
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::api_types::ShadeUpdateMotion;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum AllShadesAction {
+    Stop,
+    Open,
+    Close,
+}
+
+impl AllShadesAction {
+    fn motion(self) -> ShadeUpdateMotion {
+        match self {
+            Self::Stop => ShadeUpdateMotion::Stop,
+            Self::Open => ShadeUpdateMotion::Up,
+            Self::Close => ShadeUpdateMotion::Down,
+        }
+    }
+
+    fn past_tense(self) -> &'static str {
+        match self {
+            Self::Stop => "stopped",
+            Self::Open => "opened",
+            Self::Close => "closed",
+        }
+    }
+}
+
+/// Stop, open or close every shade in the house.
+/// A few shades are addressed at a time, and a failure to reach
+/// one shade doesn't prevent the others from being moved.
+#[derive(clap::Parser, Debug)]
+pub struct AllShadesCommand {
+    #[arg(value_enum)]
+    action: AllShadesAction,
+}
+
+impl AllShadesCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let shades = hub.list_shades(None, None).await?;
+        let motion = self.action.motion();
+
+        let failed =
+            super::stop_all::move_all(&hub, &shades, motion, self.action.past_tense()).await;
+
+        println!(
+            "{} of {} shade(s) {}",
+            shades.len() - failed.len(),
+            shades.len(),
+            self.action.past_tense()
+        );
+        if !failed.is_empty() {
+            anyhow::bail!(
+                "{} shade(s) could not be {}: {}",
+                failed.len(),
+                self.action.past_tense(),
+                failed.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
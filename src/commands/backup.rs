@@ -0,0 +1,86 @@
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// The fields that the hub stores base64 encoded, for each of the
+/// collections in the backup. These are decoded in the backup so that
+/// it is human readable; re-encoding them with standard base64 yields
+/// the values that the hub reported.
+const BASE64_FIELDS: &[(&str, &[&str])] = &[
+    ("rooms", &["name"]),
+    ("shades", &["name", "secondaryName"]),
+    ("scenes", &["name"]),
+    ("sceneCollections", &["name"]),
+];
+
+/// Write a complete backup of the hub configuration as json.
+/// This is the same as export-config, but with names decoded so that
+/// it is human readable, and is intended to be taken before making
+/// significant changes, such as a firmware update.
+#[derive(clap::Parser, Debug)]
+pub struct BackupCommand {
+    /// Write the backup to this file, rather than to stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+impl BackupCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let mut backup = hub.export_config().await?;
+        decode_base64_fields(&mut backup)?;
+
+        let json = serde_json::to_string_pretty(&backup)?;
+        match &self.output {
+            Some(path) => {
+                std::fs::write(path, format!("{json}\n"))
+                    .map_err(|err| anyhow::anyhow!("writing {}: {err:#}", path.display()))?;
+                let count = |collection: &str| {
+                    backup
+                        .get(collection)
+                        .and_then(Value::as_array)
+                        .map(Vec::len)
+                        .unwrap_or(0)
+                };
+                println!(
+                    "Backed up {} rooms, {} shades and {} scenes to {}",
+                    count("rooms"),
+                    count("shades"),
+                    count("scenes"),
+                    path.display()
+                );
+            }
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+}
+
+/// Replace the base64 encoded values of BASE64_FIELDS, and the hub
+/// name, with their decoded text
+fn decode_base64_fields(backup: &mut Value) -> anyhow::Result<()> {
+    if let Some(user_data) = backup.get_mut("userData") {
+        decode_fields(user_data, &["hubName"])?;
+    }
+    for (collection, fields) in BASE64_FIELDS {
+        if let Some(Value::Array(items)) = backup.get_mut(*collection) {
+            for item in items {
+                decode_fields(item, fields)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn decode_fields(object: &mut Value, fields: &[&str]) -> anyhow::Result<()> {
+    for field in fields {
+        if let Some(Value::String(text)) = object.get_mut(*field) {
+            let decoded = data_encoding::BASE64
+                .decode(text.as_bytes())
+                .map_err(|err| anyhow::anyhow!("decoding {field} {text}: {err:#}"))?;
+            *text = String::from_utf8(decoded)
+                .map_err(|err| anyhow::anyhow!("decoding {field} {text}: {err:#}"))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use crate::api_types::ShadeUpdateMotion;
+use std::time::{Duration, Instant};
+
+/// How often to check on the shade when using --wait
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Calibrate a shade by running it through its full range of travel.
+/// The shade will not respond to other commands until calibration
+/// is complete.
+#[derive(clap::Parser, Debug)]
+pub struct CalibrateShadeCommand {
+    /// The name or id of the shade to calibrate
+    name: String,
+
+    /// Don't prompt for confirmation before starting calibration
+    #[arg(long)]
+    yes: bool,
+
+    /// After starting calibration, wait until the shade
+    /// reports its position again
+    #[arg(long)]
+    wait: bool,
+
+    /// How many seconds to wait for the shade when using --wait
+    #[arg(long, default_value = "180", value_parser = crate::parse_duration)]
+    timeout: Duration,
+
+    /// Calibrating multiple shades at once is not supported;
+    /// this exists only to produce a helpful error
+    #[arg(long, hide = true)]
+    room: Option<String>,
+
+    /// Calibrating multiple shades at once is not supported;
+    /// this exists only to produce a helpful error
+    #[arg(long, hide = true)]
+    all: bool,
+}
+
+impl CalibrateShadeCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        if self.room.is_some() || self.all {
+            anyhow::bail!(
+                "calibrate-shade only operates on a single shade, \
+                 to avoid accidentally calibrating every shade in the house"
+            );
+        }
+
+        let hub = args.hub().await?;
+        let shade = hub.shade_by_id_or_name(&self.name).await?;
+
+        println!(
+            "Calibration will move shade {} {} through its full range of travel,",
+            shade.id,
+            shade.name()
+        );
+        println!("and it will not respond to other commands until that completes.");
+        if !self.yes && !super::confirm("Calibrate this shade?")? {
+            println!("Not calibrating shade {}", shade.id);
+            return Ok(());
+        }
+
+        hub.move_shade(shade.id, ShadeUpdateMotion::Calibrate)
+            .await?;
+        println!("Calibration of {} started", shade.name());
+
+        if !self.wait {
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            match hub.shade_by_id(shade.id).await {
+                Ok(data) if !data.timed_out && data.positions.is_some() => {
+                    println!(
+                        "{} reports position {} after {:?}",
+                        data.name(),
+                        data.positions
+                            .as_ref()
+                            .map(|p| p.describe())
+                            .unwrap_or_default(),
+                        started.elapsed()
+                    );
+                    return Ok(());
+                }
+                Ok(_) => {}
+                Err(err) => log::debug!("polling shade {}: {err:#}", shade.id),
+            }
+            if started.elapsed() >= self.timeout {
+                anyhow::bail!(
+                    "{} did not report its position within {:?}",
+                    shade.name(),
+                    self.timeout
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+/// Create a new room
+#[derive(clap::Parser, Debug)]
+pub struct CreateRoomCommand {
+    /// The name of the new room
+    name: String,
+
+    /// The color used to represent the room in the PowerView app
+    #[arg(long, default_value = "0")]
+    color_id: i32,
+
+    /// The icon used to represent the room in the PowerView app
+    #[arg(long, default_value = "0")]
+    icon_id: i32,
+}
+
+impl CreateRoomCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let room = hub
+            .create_room(&self.name, self.color_id, self.icon_id)
+            .await?;
+
+        println!("{}", room.id);
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+/// Create a new scene, optionally capturing the current
+/// positions of a set of shades as its members
+#[derive(clap::Parser, Debug)]
+pub struct CreateSceneCommand {
+    /// The name of the new scene
+    #[arg(long)]
+    name: String,
+
+    /// The name or id of the room to which the scene belongs
+    #[arg(long)]
+    room: String,
+
+    /// The name or id of a shade whose current position should be
+    /// added to the scene. May be specified multiple times.
+    #[arg(long)]
+    shade: Vec<String>,
+}
+
+impl CreateSceneCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let room = hub.room_by_name(&self.room).await?;
+
+        // Resolve and snapshot all the shades before creating the scene,
+        // so that a typo doesn't leave behind a partially populated scene
+        let mut shades = vec![];
+        for name in &self.shade {
+            let resolved = hub.shade_by_id_or_name(name).await?;
+            let shade = hub.shade_by_id(resolved.id).await?;
+            let position = shade.positions.clone().ok_or_else(|| {
+                anyhow::anyhow!("shade {name} has no existing position information! {shade:#?}")
+            })?;
+            shades.push((shade, position));
+        }
+
+        let scene = hub.create_scene(&self.name, room.id).await?;
+
+        let mut added = vec![];
+        for (shade, position) in shades {
+            if let Err(err) = hub.add_scene_member(scene.id, shade.id, position).await {
+                let added = if added.is_empty() {
+                    "none".to_string()
+                } else {
+                    added.join(", ")
+                };
+                anyhow::bail!(
+                    "Created scene {} but failed to add shade {} {}: {err:#}. \
+                     Shades that were added: {added}",
+                    scene.id,
+                    shade.id,
+                    shade.name()
+                );
+            }
+            added.push(shade.name().to_string());
+        }
+
+        println!("{}", scene.id);
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::api_types::RoomType;
+
+/// Delete a room
+#[derive(clap::Parser, Debug)]
+pub struct DeleteRoomCommand {
+    /// The name or id of the room to delete.
+    /// Names will be compared ignoring case.
+    name: String,
+
+    /// Delete the room even if it still contains shades.
+    /// Those shades will be moved to the default room.
+    #[arg(long)]
+    force: bool,
+
+    /// Don't prompt for confirmation before deleting the room
+    #[arg(long)]
+    yes: bool,
+}
+
+impl DeleteRoomCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let room = hub.room_by_name(&self.name).await?;
+        if matches!(room.room_type, RoomType::DefaultRoom) {
+            anyhow::bail!(
+                "Room {} {} is the default room and cannot be deleted",
+                room.id,
+                room.name
+            );
+        }
+
+        let shades = hub.list_shades(None, Some(room.id)).await?;
+        if !shades.is_empty() && !self.force {
+            anyhow::bail!(
+                "Room {} {} still contains {} shade(s). \
+                 Use --force to move them to the default room and delete it anyway",
+                room.id,
+                room.name,
+                shades.len()
+            );
+        }
+
+        if shades.is_empty() {
+            println!("Room {} {} will be deleted.", room.id, room.name);
+        } else {
+            println!(
+                "Room {} {} will be deleted and its {} shade(s) moved to the default room.",
+                room.id,
+                room.name,
+                shades.len()
+            );
+        }
+        if !self.yes && !super::confirm("Delete this room?")? {
+            println!("Not deleting room {}", room.id);
+            return Ok(());
+        }
+
+        if !shades.is_empty() {
+            let default_room = hub
+                .list_rooms()
+                .await?
+                .into_iter()
+                .find(|r| matches!(r.room_type, RoomType::DefaultRoom))
+                .ok_or_else(|| anyhow::anyhow!("Hub has no default room"))?;
+
+            for shade in &shades {
+                hub.change_shade_room(shade.id, default_room.id).await?;
+                println!(
+                    "Moved shade {} {} to room {}",
+                    shade.id,
+                    shade.name(),
+                    default_room.name
+                );
+            }
+        }
+
+        hub.delete_room(room.id).await?;
+
+        println!("Deleted room {} {}", room.id, room.name);
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::http_helpers::HubError;
+
+/// Delete a scene
+#[derive(clap::Parser, Debug)]
+pub struct DeleteSceneCommand {
+    /// The name or id of the scene to delete.
+    /// Names will be compared ignoring case.
+    name: String,
+
+    /// Don't prompt for confirmation before deleting the scene
+    #[arg(long)]
+    yes: bool,
+}
+
+impl DeleteSceneCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let scene = hub.scene_by_name(&self.name).await?;
+        let num_members = hub
+            .list_scene_members()
+            .await?
+            .get(&scene.id)
+            .map(|members| members.len())
+            .unwrap_or(0);
+
+        println!(
+            "Scene {} {} has {num_members} member shade(s)",
+            scene.id, scene.name
+        );
+        if !self.yes && !super::confirm("Delete this scene?")? {
+            println!("Not deleting scene {}", scene.id);
+            return Ok(());
+        }
+
+        if let Err(err) = hub.delete_scene(scene.id).await {
+            let not_found = err
+                .chain()
+                .any(|cause| matches!(cause.downcast_ref(), Some(HubError::NotFound(_))));
+            if not_found {
+                anyhow::bail!(
+                    "The hub reports that scene {} no longer exists; \
+                     it may have been deleted by someone else",
+                    scene.id
+                );
+            }
+            return Err(err);
+        }
+
+        println!("Deleted scene {} {}", scene.id, scene.name);
+        Ok(())
+    }
+}
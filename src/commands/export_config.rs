@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+/// Export the rooms, shades, scenes and schedules configured in the
+/// hub as json, so that you can keep a record of your setup
+#[derive(clap::Parser, Debug)]
+pub struct ExportConfigCommand {
+    /// Write the json to this file, rather than to stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Format the json so that it is easier for humans to read
+    #[arg(long)]
+    pretty: bool,
+}
+
+impl ExportConfigCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let config = hub.export_config().await?;
+
+        let json = if self.pretty {
+            serde_json::to_string_pretty(&config)?
+        } else {
+            serde_json::to_string(&config)?
+        };
+
+        match &self.output {
+            Some(path) => std::fs::write(path, format!("{json}\n"))
+                .map_err(|err| anyhow::anyhow!("writing {}: {err:#}", path.display()))?,
+            None => println!("{json}"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use tabout::{Alignment, Column};
+
+/// Show the firmware versions of the hub and of each shade
+#[derive(clap::Parser, Debug)]
+pub struct FirmwareCommand {}
+
+impl FirmwareCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let user_data = hub.get_user_data().await?;
+        let shades = hub.list_shades(None, None).await?;
+
+        let columns = &[
+            Column {
+                name: "DEVICE".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "COMPONENT".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "VERSION".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+
+        let mut rows = vec![];
+        let hub_name = user_data.hub_name.to_string();
+        for (component, fw) in [
+            ("Main Processor", &user_data.firmware.main_processor),
+            ("Radio", &user_data.firmware.radio),
+        ] {
+            let component = match &fw.name {
+                Some(name) => format!("{component} ({name})"),
+                None => component.to_string(),
+            };
+            rows.push(vec![hub_name.clone(), component, fw.to_string()]);
+        }
+
+        for shade in &shades {
+            rows.push(vec![
+                shade.name().to_string(),
+                "Firmware".to_string(),
+                shade
+                    .firmware
+                    .as_ref()
+                    .map(|fw| fw.to_string())
+                    .unwrap_or_default(),
+            ]);
+            if let Some(motor) = &shade.motor {
+                rows.push(vec![
+                    shade.name().to_string(),
+                    "Motor".to_string(),
+                    motor.to_string(),
+                ]);
+            }
+        }
+
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        Ok(())
+    }
+}
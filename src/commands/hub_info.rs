@@ -1,9 +1,18 @@
 /// Show diagnostic information for the hub
 #[derive(clap::Parser, Debug)]
-pub struct HubInfoCommand {}
+pub struct HubInfoCommand {
+    /// Print the json returned by the hub without interpreting it
+    #[arg(long)]
+    raw: bool,
+}
 impl HubInfoCommand {
     pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
         let hub = args.hub().await?;
+        if self.raw {
+            let user_data = hub.get_user_data_raw().await?;
+            println!("{}", serde_json::to_string_pretty(&user_data)?);
+            return Ok(());
+        }
         let user_data = hub.get_user_data().await?;
         println!("{user_data:#?}");
         Ok(())
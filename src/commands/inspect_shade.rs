@@ -1,18 +1,156 @@
+use crate::api_types::{ShadeData, ShadePosition};
+use crate::hub::Hub;
+use tabout::{Alignment, Column};
+
 /// Show diagnostic information about a shade
 #[derive(clap::Parser, Debug)]
 pub struct InspectShadeCommand {
-    /// The name or id of the shade to inspect.
+    /// The names or ids of the shades to inspect.
     /// Names will be compared ignoring case.
-    name: String,
+    #[arg(required = true)]
+    names: Vec<String>,
+
+    /// Print the json returned by the hub without interpreting it.
+    /// Passing the shade id rather than its name allows this to
+    /// work even when the shade list cannot be parsed.
+    #[arg(long)]
+    raw: bool,
+
+    /// How to present the shade information.
+    /// `debug` shows every field as parsed from the hub, while
+    /// `detailed` shows a human friendly summary.
+    #[arg(long, value_enum, default_value = "debug", conflicts_with = "raw")]
+    format: InspectFormat,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum InspectFormat {
+    Debug,
+    Detailed,
 }
 
 impl InspectShadeCommand {
     pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
         let hub = args.hub().await?;
 
-        let shade = hub.shade_by_name(&self.name).await?;
+        let results =
+            futures::future::join_all(self.names.iter().map(|name| self.inspect(&hub, name))).await;
+
+        let mut failed = 0;
+        for (idx, (name, result)) in self.names.iter().zip(results).enumerate() {
+            if idx > 0 {
+                println!("{}", "-".repeat(40));
+            }
+            match result {
+                Ok(output) => println!("{output}"),
+                Err(err) => {
+                    println!("{name}: {err:#}");
+                    failed += 1;
+                }
+            }
+        }
 
-        println!("{shade:#?}");
+        if failed > 0 {
+            anyhow::bail!("{failed} shade(s) could not be inspected");
+        }
         Ok(())
     }
+
+    async fn inspect(&self, hub: &Hub, name: &str) -> anyhow::Result<String> {
+        if self.raw {
+            // Avoid parsing the shade when we were given its id,
+            // as the point of --raw is to see data that we may
+            // be unable to parse
+            let shade_id = match name.parse::<i32>() {
+                Ok(id) => id,
+                Err(_) => hub.shade_by_id_or_name(name).await?.id,
+            };
+            let shade = hub.shade_by_id_raw(shade_id).await?;
+            return Ok(serde_json::to_string_pretty(&shade)?);
+        }
+
+        let shade = hub.shade_by_id_or_name(name).await?;
+
+        match self.format {
+            InspectFormat::Debug => Ok(format!("{shade:#?}")),
+            InspectFormat::Detailed => format_detailed(&shade),
+        }
+    }
+}
+
+fn describe_position(raw: Option<u16>) -> String {
+    match raw {
+        Some(raw) => format!("{}% (raw {raw})", ShadePosition::pos_to_percent(raw)),
+        None => "-".to_string(),
+    }
+}
+
+fn format_detailed(shade: &ShadeData) -> anyhow::Result<String> {
+    let columns = &[
+        Column {
+            name: "FIELD".to_string(),
+            alignment: Alignment::Left,
+        },
+        Column {
+            name: "VALUE".to_string(),
+            alignment: Alignment::Left,
+        },
+    ];
+
+    let capabilities = shade
+        .capabilities
+        .flags()
+        .iter_names()
+        .map(|(name, _)| name.to_lowercase().replace('_', " "))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let percent = |pct: Option<u8>| match pct {
+        Some(pct) => format!("{pct}%"),
+        None => "-".to_string(),
+    };
+
+    let firmware = match &shade.firmware {
+        Some(fw) => fw.to_string(),
+        None => "-".to_string(),
+    };
+
+    let positions = shade.positions.as_ref();
+
+    let rows = vec![
+        vec!["Id".to_string(), shade.id.to_string()],
+        vec!["Name".to_string(), shade.name().to_string()],
+        vec!["Type".to_string(), format!("{:?}", shade.shade_type)],
+        vec![
+            "Capabilities".to_string(),
+            format!("{:?}: {capabilities}", shade.capabilities),
+        ],
+        vec![
+            "Position 1".to_string(),
+            describe_position(positions.map(|p| p.position_1)),
+        ],
+        vec![
+            "Position 2".to_string(),
+            describe_position(positions.and_then(|p| p.position_2)),
+        ],
+        vec!["Battery".to_string(), percent(shade.battery_percent())],
+        vec![
+            "Battery Kind".to_string(),
+            format!("{:?}", shade.battery_kind),
+        ],
+        vec![
+            "Signal Strength".to_string(),
+            percent(shade.signal_strength_percent()),
+        ],
+        vec!["Firmware".to_string(), firmware],
+        vec![
+            "Serial Number".to_string(),
+            shade
+                .serial_number
+                .clone()
+                .unwrap_or_else(|| "-".to_string()),
+        ],
+    ];
+
+    Ok(tabout::tabulate_output_as_string(columns, &rows)?)
 }
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use tabout::{Alignment, Column};
+
+/// List the automations that the hub will run
+#[derive(clap::Parser, Debug)]
+pub struct ListAutomationsCommand {}
+
+impl ListAutomationsCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let automations = hub.list_automations().await?;
+        let scene_by_id: HashMap<_, _> = hub
+            .list_scenes()
+            .await?
+            .into_iter()
+            .map(|scene| (scene.id, scene.name.to_string()))
+            .collect();
+        let collection_by_id: HashMap<_, _> = if automations
+            .iter()
+            .any(|a| a.action.scene_collection_id.is_some())
+        {
+            hub.list_scene_collections()
+                .await?
+                .into_iter()
+                .map(|c| (c.id, c.name.to_string()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let columns = &[
+            Column {
+                name: "NAME".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "ENABLED".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "TRIGGER".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "SCENE".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+
+        let mut rows = vec![];
+        for automation in &automations {
+            let scene = match (
+                automation.action.scene_id,
+                automation.action.scene_collection_id,
+            ) {
+                (Some(id), _) => scene_by_id
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| id.to_string()),
+                (None, Some(id)) => collection_by_id
+                    .get(&id)
+                    .map(|name| format!("{name} (collection)"))
+                    .unwrap_or_else(|| format!("{id} (collection)")),
+                (None, None) => String::new(),
+            };
+
+            rows.push(vec![
+                automation
+                    .name
+                    .as_ref()
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| automation.id.to_string()),
+                if automation.enabled { "yes" } else { "no" }.to_string(),
+                automation.event.to_string(),
+                scene,
+            ]);
+        }
+
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::time::Duration;
 
 /// Discover and list the hubs on your network
@@ -6,25 +7,49 @@ pub struct ListHubsCommand {
     /// How long to wait for discovery to complete, in seconds
     #[arg(long, default_value = "15")]
     timeout: u64,
+
+    /// Stop as soon as this many distinct hubs have been found,
+    /// rather than waiting for the full timeout
+    #[arg(long, conflicts_with = "exit_on_first")]
+    count: Option<usize>,
+
+    /// Stop as soon as the first hub has been found.
+    /// This is equivalent to --count 1
+    #[arg(long)]
+    exit_on_first: bool,
 }
 
 impl ListHubsCommand {
     pub async fn run(&self, _args: &crate::Args) -> anyhow::Result<()> {
+        let count = if self.exit_on_first {
+            Some(1)
+        } else {
+            self.count
+        };
+
         let mut hubs =
             crate::discovery::resolve_hubs(Some(Duration::from_secs(self.timeout))).await?;
 
+        // Hubs may respond more than once; key them by serial number,
+        // or by address if they aren't responding
+        let mut seen = HashSet::new();
+
         while let Some(hub) = hubs.recv().await {
             if let Some(user_data) = &hub.user_data {
-                println!(
-                    "{addr} SN={serial} MAC={mac} {name}",
-                    addr = hub.hub.addr(),
-                    serial = user_data.serial_number,
-                    name = user_data.hub_name.to_string(),
-                    mac = user_data.mac_address
-                );
+                if !seen.insert(user_data.serial_number.clone()) {
+                    continue;
+                }
+                println!("{user_data} MAC={}", user_data.mac_address);
             } else {
+                if !seen.insert(hub.hub.addr().to_string()) {
+                    continue;
+                }
                 println!("{} (Not responding)", hub.hub.addr());
             }
+
+            if count.map(|count| seen.len() >= count).unwrap_or(false) {
+                break;
+            }
         }
 
         Ok(())
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use tabout::{Alignment, Column};
+
+/// List the repeaters that extend the range of the hub
+#[derive(clap::Parser, Debug)]
+pub struct ListRepeatersCommand {
+    /// Print the repeaters as json
+    #[arg(long)]
+    json: bool,
+}
+
+impl ListRepeatersCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let repeaters = hub.list_repeaters().await?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&repeaters)?);
+            return Ok(());
+        }
+
+        let room_by_id: HashMap<_, _> = hub
+            .list_rooms()
+            .await?
+            .into_iter()
+            .map(|room| (room.id, room.name.to_string()))
+            .collect();
+
+        let columns = &[
+            Column {
+                name: "ID".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "REPEATER".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "ROOM".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "COLOR".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "FIRMWARE".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+
+        let rows: Vec<_> = repeaters
+            .iter()
+            .map(|repeater| {
+                vec![
+                    repeater.id.to_string(),
+                    repeater
+                        .name
+                        .as_ref()
+                        .map(|name| name.to_string())
+                        .unwrap_or_default(),
+                    repeater
+                        .room_id
+                        .and_then(|id| room_by_id.get(&id).cloned())
+                        .unwrap_or_default(),
+                    repeater
+                        .color
+                        .as_ref()
+                        .map(|c| c.to_string())
+                        .unwrap_or_default(),
+                    repeater
+                        .firmware
+                        .as_ref()
+                        .map(|fw| fw.to_string())
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use crate::api_types::RoomData;
+use serde::Serialize;
+use std::collections::HashMap;
+use tabout::{Alignment, Column};
+
+/// List rooms and the number of shades in each of them
+#[derive(clap::Parser, Debug)]
+pub struct ListRoomsCommand {
+    /// Emit the rooms as json rather than a table
+    #[arg(long)]
+    json: bool,
+}
+
+/// The json representation of a room, as printed by the
+/// room related commands
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonRoom {
+    id: i32,
+    name: String,
+    #[serde(rename = "type")]
+    room_type: String,
+    order: i32,
+    color_id: i32,
+    icon_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shade_count: Option<usize>,
+}
+
+impl JsonRoom {
+    pub fn new(room: &RoomData, shade_count: Option<usize>) -> Self {
+        Self {
+            id: room.id,
+            name: room.name.to_string(),
+            room_type: format!("{:?}", room.room_type),
+            order: room.order,
+            color_id: room.color_id,
+            icon_id: room.icon_id,
+            shade_count,
+        }
+    }
+}
+
+impl ListRoomsCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let rooms = hub.list_rooms().await?;
+        let mut shade_count_by_room: HashMap<i32, usize> = HashMap::new();
+        for shade in hub.list_shades(None, None).await? {
+            if let Some(room_id) = shade.room_id {
+                *shade_count_by_room.entry(room_id).or_default() += 1;
+            }
+        }
+
+        if self.json {
+            let rooms: Vec<JsonRoom> = rooms
+                .iter()
+                .map(|room| {
+                    let shade_count = shade_count_by_room.get(&room.id).copied().unwrap_or(0);
+                    JsonRoom::new(room, Some(shade_count))
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rooms)?);
+            return Ok(());
+        }
+
+        let columns = &[
+            Column {
+                name: "ID".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "ROOM".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "SHADES".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "TYPE".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+        let mut rows = vec![];
+        for room in &rooms {
+            rows.push(vec![
+                room.id.to_string(),
+                room.name.to_string(),
+                shade_count_by_room
+                    .get(&room.id)
+                    .copied()
+                    .unwrap_or(0)
+                    .to_string(),
+                room.room_type.describe().to_string(),
+            ]);
+        }
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use tabout::{Alignment, Column};
+
+/// List scene collections and the scenes that they activate
+#[derive(clap::Parser, Debug)]
+pub struct ListSceneCollectionsCommand {}
+
+impl ListSceneCollectionsCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let collections = hub.list_scene_collections().await?;
+        let mut members_by_collection = hub.list_scene_collection_members().await?;
+        let scene_by_id: HashMap<_, _> = hub
+            .list_scenes()
+            .await?
+            .into_iter()
+            .map(|scene| (scene.id, scene))
+            .collect();
+
+        let columns = &[
+            Column {
+                name: "ID".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "COLLECTION".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "SCENE".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+
+        let mut rows = vec![];
+        for collection in &collections {
+            let members = members_by_collection
+                .remove(&collection.id)
+                .unwrap_or_default();
+            if members.is_empty() {
+                rows.push(vec![
+                    collection.id.to_string(),
+                    collection.name.to_string(),
+                    String::new(),
+                ]);
+            }
+            for member in members {
+                let scene_name = scene_by_id
+                    .get(&member.scene_id)
+                    .map(|scene| scene.name.to_string())
+                    .unwrap_or_else(|| member.scene_id.to_string());
+                rows.push(vec![
+                    collection.id.to_string(),
+                    collection.name.to_string(),
+                    scene_name,
+                ]);
+            }
+        }
+
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use crate::api_types::{SceneMember, ShadePosition};
+use serde::Serialize;
 use std::collections::HashMap;
 use tabout::{Alignment, Column};
 
@@ -7,17 +9,64 @@ pub struct ListScenesCommand {
     /// Only return shades in the specified room
     #[clap(long)]
     room: Option<String>,
+
+    /// Emit the scenes and their members, including raw positions,
+    /// as json rather than a table
+    #[clap(long)]
+    json: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonScene {
+    id: i32,
+    name: String,
+    room_id: i32,
+    order: i32,
+    network_number: i32,
+    hk_assist: bool,
+    members: Vec<JsonSceneMember>,
+}
+
+#[derive(Serialize, Debug)]
+struct JsonSceneMember {
+    id: i32,
+    shade_id: i32,
+    shade_name: Option<String>,
+    pos_kind_1: String,
+    position_1: u16,
+    percent_1: u8,
+    pos_kind_2: Option<String>,
+    position_2: Option<u16>,
+    percent_2: Option<u8>,
+}
+
+impl JsonSceneMember {
+    fn new(member: &SceneMember, shade_name: Option<String>) -> Self {
+        let pos = &member.positions;
+        Self {
+            id: member.id,
+            shade_id: member.shade_id,
+            shade_name,
+            pos_kind_1: format!("{:?}", pos.pos_kind_1),
+            position_1: pos.position_1,
+            percent_1: pos.pos1_percent(),
+            pos_kind_2: pos.pos_kind_2.map(|kind| format!("{kind:?}")),
+            position_2: pos.position_2,
+            percent_2: pos.position_2.map(ShadePosition::pos_to_percent),
+        }
+    }
 }
 
 impl ListScenesCommand {
     pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
         let hub = args.hub().await?;
-        let mut scenes = hub.list_scenes().await?;
-
-        if let Some(room) = &self.room {
-            let room = hub.room_by_name(room).await?;
-            scenes.retain(|scene| scene.room_id == room.id);
-        }
+        let scenes = match &self.room {
+            Some(room) => {
+                let room = hub.room_by_name(room).await?;
+                hub.list_scenes_by_room(room.id).await?
+            }
+            None => hub.list_scenes().await?,
+        };
 
         let shade_by_id: HashMap<_, _> = hub
             .list_shades(None, None)
@@ -28,6 +77,39 @@ impl ListScenesCommand {
 
         let mut members_by_scene = hub.list_scene_members().await?;
 
+        if self.json {
+            let scenes: Vec<JsonScene> = scenes
+                .into_iter()
+                .map(|scene| {
+                    let members = members_by_scene
+                        .get(&scene.id)
+                        .map(|members| {
+                            members
+                                .iter()
+                                .map(|m| {
+                                    let shade_name = shade_by_id
+                                        .get(&m.shade_id)
+                                        .map(|shade| shade.name().to_string());
+                                    JsonSceneMember::new(m, shade_name)
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    JsonScene {
+                        id: scene.id,
+                        name: scene.name.to_string(),
+                        room_id: scene.room_id,
+                        order: scene.order,
+                        network_number: scene.network_number,
+                        hk_assist: scene.hk_assist,
+                        members,
+                    }
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&scenes)?);
+            return Ok(());
+        }
+
         let columns = &[
             Column {
                 name: "SCENE/SHADES".to_string(),
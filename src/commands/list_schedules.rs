@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use tabout::{Alignment, Column};
+
+/// List the scheduled events that activate scenes at particular
+/// times of day, or relative to sunrise and sunset
+#[derive(clap::Parser, Debug)]
+pub struct ListSchedulesCommand {
+    /// Print the scheduled events as json
+    #[arg(long)]
+    json: bool,
+}
+
+impl ListSchedulesCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let events = hub.list_scheduled_events().await?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&events)?);
+            return Ok(());
+        }
+
+        // Events may refer to scenes that have since been deleted;
+        // those are shown using their id
+        let scene_by_id: HashMap<_, _> = hub
+            .list_scenes()
+            .await?
+            .into_iter()
+            .map(|scene| (scene.id, scene.name.to_string()))
+            .collect();
+        let collection_by_id: HashMap<_, _> = if events
+            .iter()
+            .any(|e| e.action.scene_collection_id.is_some())
+        {
+            hub.list_scene_collections()
+                .await?
+                .into_iter()
+                .map(|c| (c.id, c.name.to_string()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let columns = &[
+            Column {
+                name: "ID".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "ENABLED".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "DAYS".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "TRIGGER".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "SCENE".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+
+        let mut rows = vec![];
+        for event in &events {
+            let scene = match (event.action.scene_id, event.action.scene_collection_id) {
+                (Some(id), _) => scene_by_id
+                    .get(&id)
+                    .cloned()
+                    .unwrap_or_else(|| id.to_string()),
+                (None, Some(id)) => collection_by_id
+                    .get(&id)
+                    .map(|name| format!("{name} (collection)"))
+                    .unwrap_or_else(|| format!("{id} (collection)")),
+                (None, None) => String::new(),
+            };
+
+            rows.push(vec![
+                event.id.to_string(),
+                if event.enabled { "yes" } else { "no" }.to_string(),
+                event.days.to_string(),
+                event.event.to_string(),
+                scene,
+            ]);
+        }
+
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
 use crate::api_types::ShadeCapabilityFlags;
+use crate::hub::ResolvedShadeData;
 use std::collections::BTreeMap;
+use std::io::IsTerminal;
 use tabout::{Alignment, Column};
 
 /// List shades and their current positions
@@ -8,6 +10,49 @@ pub struct ListShadesCommand {
     /// Only return shades in the specified room
     #[clap(long)]
     room: Option<String>,
+
+    /// Only return shades in the room with the specified id.
+    /// Unlike --room, this doesn't need to look up the room.
+    #[clap(long, conflicts_with = "room")]
+    room_id: Option<i32>,
+
+    /// Only return shades whose name matches this glob pattern,
+    /// such as "*Roller*". Names will be compared ignoring case.
+    #[clap(long, conflicts_with = "raw")]
+    shade_filter: Option<String>,
+
+    /// Print the json returned by the hub without interpreting it
+    #[clap(long)]
+    raw: bool,
+
+    /// Only show shades that have problems, such as low batteries,
+    /// communication timeouts or unknown positions
+    #[clap(long)]
+    problems: bool,
+
+    /// When using --problems, the battery percentage below which
+    /// a shade is considered to have a problem
+    #[clap(long, default_value = "20")]
+    battery_threshold: u8,
+
+    /// Show additional information, such as the serial number
+    /// of each shade, where the hub reports it
+    #[clap(long)]
+    verbose: bool,
+
+    /// Add an ID column containing the numeric id of each shade,
+    /// which is useful when processing the output in scripts
+    #[clap(long)]
+    show_ids: bool,
+}
+
+/// Whether it is appropriate to emit color escape sequences to stdout.
+/// <https://no-color.org/>
+fn use_color() -> bool {
+    std::io::stdout().is_terminal()
+        && std::env::var_os("NO_COLOR")
+            .map(|v| v.is_empty())
+            .unwrap_or(true)
 }
 
 impl ListShadesCommand {
@@ -16,22 +61,44 @@ impl ListShadesCommand {
 
         let opt_room_id = match &self.room {
             Some(name) => Some(hub.room_by_name(name).await?.id),
-            None => None,
+            None => self.room_id,
         };
 
+        if self.raw {
+            let shades = hub.list_shades_raw(None, opt_room_id).await?;
+            println!("{}", serde_json::to_string_pretty(&shades)?);
+            return Ok(());
+        }
+
         let rooms = hub.list_rooms().await?;
 
-        let shades = hub.list_shades(None, opt_room_id).await?;
+        let shades = match &self.shade_filter {
+            Some(pattern) => hub
+                .shades_matching(pattern)
+                .await?
+                .into_iter()
+                .map(ResolvedShadeData::into_inner)
+                .filter(|shade| opt_room_id.is_none() || shade.room_id == opt_room_id)
+                .collect(),
+            None => hub.list_shades(None, opt_room_id).await?,
+        };
 
         let mut shades_by_room = BTreeMap::new();
         for shade in shades {
             let room = shades_by_room
                 .entry(shade.room_id.unwrap_or(0))
-                .or_insert_with(|| vec![]);
+                .or_insert_with(Vec::new);
             room.push(shade);
         }
 
-        let columns = &[
+        let mut columns = vec![];
+        if self.show_ids {
+            columns.push(Column {
+                name: "ID".to_string(),
+                alignment: Alignment::Right,
+            });
+        }
+        columns.extend([
             Column {
                 name: "ROOM".to_string(),
                 alignment: Alignment::Left,
@@ -44,33 +111,106 @@ impl ListShadesCommand {
                 name: "POSITION".to_string(),
                 alignment: Alignment::Right,
             },
-        ];
+            Column {
+                name: "SIGNAL".to_string(),
+                alignment: Alignment::Right,
+            },
+        ]);
+        if self.verbose {
+            columns.push(Column {
+                name: "SERIAL".to_string(),
+                alignment: Alignment::Left,
+            });
+        }
+        if self.problems {
+            // Keep this as the last column, so that the escape sequences
+            // used to color it don't throw off the alignment of the table
+            columns.push(Column {
+                name: "PROBLEMS".to_string(),
+                alignment: Alignment::Left,
+            });
+        }
+
+        let color = use_color();
         let mut rows = vec![];
         for room_data in &rooms {
             if let Some(shades) = shades_by_room.get(&room_data.id) {
                 for shade in shades {
-                    if let Some(pos) = shade.positions.as_ref() {
-                        rows.push(vec![
-                            room_data.name.to_string(),
-                            shade.name().to_string(),
-                            pos.describe_pos1(),
-                        ]);
-                        if shade
-                            .capabilities
-                            .flags()
-                            .contains(ShadeCapabilityFlags::SECONDARY_RAIL)
-                        {
-                            rows.push(vec![
+                    let mut extra = vec![];
+                    if self.problems {
+                        let problems = shade.problems(self.battery_threshold);
+                        if problems.is_empty() {
+                            continue;
+                        }
+                        let problems = problems
+                            .iter()
+                            .map(|p| p.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        extra.push(if color {
+                            format!("\u{1b}[31m{problems}\u{1b}[0m")
+                        } else {
+                            problems
+                        });
+                    }
+
+                    let signal = shade
+                        .signal_strength_percent()
+                        .map(|pct| format!("{pct}%"))
+                        .unwrap_or_default();
+                    let mut trailing = vec![signal];
+                    if self.verbose {
+                        trailing.push(shade.serial_number.clone().unwrap_or_default());
+                    }
+                    trailing.extend(extra);
+
+                    let leading: Vec<String> = if self.show_ids {
+                        vec![shade.id.to_string()]
+                    } else {
+                        vec![]
+                    };
+
+                    match shade.positions.as_ref() {
+                        Some(pos) => {
+                            let mut row = leading.clone();
+                            row.extend([
+                                room_data.name.to_string(),
+                                shade.name().to_string(),
+                                pos.describe_pos1(),
+                            ]);
+                            row.extend(trailing.iter().cloned());
+                            rows.push(row);
+                            if shade
+                                .capabilities
+                                .flags()
+                                .contains(ShadeCapabilityFlags::SECONDARY_RAIL)
+                            {
+                                let mut row = leading.clone();
+                                row.extend([
+                                    room_data.name.to_string(),
+                                    shade.secondary_name(),
+                                    pos.describe_pos2(),
+                                ]);
+                                row.extend(trailing);
+                                rows.push(row);
+                            }
+                        }
+                        None if self.problems => {
+                            let mut row = leading.clone();
+                            row.extend([
                                 room_data.name.to_string(),
-                                shade.secondary_name(),
-                                pos.describe_pos2(),
+                                shade.name().to_string(),
+                                String::new(),
                             ]);
+                            row.extend(trailing);
+                            rows.push(row);
                         }
+                        None => {}
                     }
                 }
             }
         }
-        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        println!("{}", tabout::tabulate_output_as_string(&columns, &rows)?);
         Ok(())
     }
 }
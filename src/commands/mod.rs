@@ -1,8 +1,56 @@
 pub mod activate_scene;
+pub mod activate_scene_collection;
+pub mod activate_scenes;
+pub mod add_shade_to_scene;
+pub mod all_shades;
+pub mod backup;
+pub mod calibrate_shade;
+pub mod create_room;
+pub mod create_scene;
+pub mod delete_room;
+pub mod delete_scene;
+pub mod export_config;
+pub mod firmware;
 pub mod hub_info;
 pub mod inspect_shade;
+pub mod list_automations;
 pub mod list_hubs;
+pub mod list_repeaters;
+pub mod list_rooms;
+pub mod list_scene_collections;
 pub mod list_scenes;
+pub mod list_schedules;
 pub mod list_shades;
+pub mod move_room;
 pub mod move_shade;
+pub mod reboot_hub;
+pub mod refresh_battery;
+pub mod refresh_position;
+pub mod remove_shade_from_scene;
+pub mod rename_room;
+pub mod rename_shade;
+pub mod reorder_scene;
+pub mod reorder_shade;
 pub mod serve_mqtt;
+pub mod set_favorite;
+pub mod set_hub_led;
+pub mod set_power_source;
+pub mod show_automation_hook;
+pub mod status;
+pub mod stop_all;
+pub mod test_automation_hook;
+pub mod watch_shades;
+
+/// Ask the user to confirm an action on the terminal.
+/// Returns true only if they answered yes.
+pub fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    use std::io::Write;
+
+    print!("{prompt} [y/N] ");
+    std::io::stdout().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes"))
+}
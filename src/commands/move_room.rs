@@ -0,0 +1,136 @@
+use crate::api_types::{
+    PositionKind, ShadeCapabilityFlags, ShadeData, ShadePosition, ShadeUpdateMotion,
+};
+use crate::hub::Hub;
+use tabout::{Alignment, Column};
+
+#[derive(clap::Args, Debug)]
+#[group(required = true)]
+struct TargetPosition {
+    #[arg(long, conflicts_with = "percent")]
+    motion: Option<ShadeUpdateMotion>,
+    #[arg(long)]
+    percent: Option<u8>,
+}
+
+/// Move all of the shades in a room to the same position
+#[derive(clap::Parser, Debug)]
+pub struct MoveRoomCommand {
+    /// The name or id of the room.
+    /// Names will be compared ignoring case.
+    name: String,
+
+    #[command(flatten)]
+    target_position: TargetPosition,
+
+    /// Set the position of the middle rail of top-down-bottom-up
+    /// shades, rather than the bottom rail. Shades that don't have
+    /// a middle rail are skipped.
+    #[arg(long, requires = "percent")]
+    secondary: bool,
+}
+
+impl MoveRoomCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let room = hub.room_by_name(&self.name).await?;
+        let shades = hub.list_shades(None, Some(room.id)).await?;
+
+        let mut rows = vec![];
+        let mut failed = 0;
+
+        // Move the shades one at a time, so that we don't swamp the hub
+        for shade in shades {
+            let name = if self.secondary {
+                shade.secondary_name()
+            } else {
+                shade.name().to_string()
+            };
+
+            if self.secondary
+                && !shade
+                    .capabilities
+                    .flags()
+                    .contains(ShadeCapabilityFlags::SECONDARY_RAIL)
+            {
+                rows.push(vec![name, "skipped; no middle rail".to_string()]);
+                continue;
+            }
+
+            match self.move_one(&hub, &shade).await {
+                Ok(shade) => {
+                    let position = shade
+                        .positions
+                        .as_ref()
+                        .map(|p| {
+                            if self.secondary {
+                                p.describe_pos2()
+                            } else {
+                                p.describe_pos1()
+                            }
+                        })
+                        .unwrap_or_default();
+                    rows.push(vec![name, position]);
+                }
+                Err(err) => {
+                    rows.push(vec![name, format!("failed: {err:#}")]);
+                    failed += 1;
+                }
+            }
+        }
+
+        let columns = &[
+            Column {
+                name: "SHADE".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "POSITION".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+
+        if failed > 0 {
+            anyhow::bail!("{failed} shade(s) in room {} could not be moved", room.name);
+        }
+        Ok(())
+    }
+
+    async fn move_one(&self, hub: &Hub, shade: &ShadeData) -> anyhow::Result<ShadeData> {
+        if let Some(motion) = self.target_position.motion {
+            return hub.move_shade(shade.id, motion).await;
+        }
+
+        let percent = self
+            .target_position
+            .percent
+            .ok_or_else(|| anyhow::anyhow!("One of --motion or --percent is required"))?;
+        let absolute = ShadePosition::percent_to_pos(percent);
+
+        let position = match (shade.positions.clone(), self.secondary) {
+            (Some(mut position), false) => {
+                position.position_1 = absolute;
+                position
+            }
+            (Some(mut position), true) => {
+                position.position_2.replace(absolute);
+                if position.pos_kind_2.is_none() {
+                    position.pos_kind_2.replace(PositionKind::SecondaryRail);
+                }
+                position
+            }
+            // The hub doesn't know where this shade is, but we can
+            // still tell it where the bottom rail should go
+            (None, false) => {
+                ShadePosition::from_percent_pair(PositionKind::PrimaryRail, percent, None, None)
+            }
+            (None, true) => {
+                anyhow::bail!("shade has no existing position information")
+            }
+        };
+
+        hub.change_shade_position(shade.id, position).await
+    }
+}
@@ -1,4 +1,6 @@
-use crate::api_types::{ShadePosition, ShadeUpdateMotion};
+use crate::api_types::{ShadeData, ShadeUpdateMotion};
+use crate::hub::{Hub, ResolvedShadeData};
+use std::time::{Duration, Instant};
 
 #[derive(clap::Args, Debug)]
 #[group(required = true)]
@@ -7,6 +9,9 @@ struct TargetPosition {
     motion: Option<ShadeUpdateMotion>,
     #[arg(long, group = "position")]
     percent: Option<u8>,
+    /// Move the shade to its favorite position
+    #[arg(long, conflicts_with_all = ["motion", "percent"])]
+    favorite: bool,
 }
 
 /// Move or set the position of a shade
@@ -14,37 +19,213 @@ struct TargetPosition {
 pub struct MoveShadeCommand {
     /// The name or id of the shade to open.
     /// Names will be compared ignoring case.
-    name: String,
+    #[arg(required_unless_present_any = ["room", "group", "shade_filter"])]
+    name: Option<String>,
+
+    /// Instead of moving a single shade, move each of the shades in
+    /// the room with this name or id, one after the other
+    #[arg(long, conflicts_with = "name")]
+    room: Option<String>,
+
+    /// Instead of moving a single shade, move each of the shades whose
+    /// name matches this glob pattern, such as "Bedroom*", one after
+    /// the other. Names will be compared ignoring case.
+    #[arg(long, conflicts_with_all = ["name", "room"])]
+    shade_filter: Option<String>,
+
+    /// Instead of moving a single shade, move each of the shades
+    /// that the hub has assigned to this group id, one after the other
+    #[arg(long, conflicts_with_all = ["name", "room", "shade_filter"])]
+    group: Option<i32>,
+
     #[command(flatten)]
     target_position: TargetPosition,
+
+    /// After moving the shade, wait until it reports that it has
+    /// reached the target position. Only supported when moving a
+    /// single shade with --percent, --favorite, or --motion up or down.
+    #[arg(long, conflicts_with_all = ["room", "group", "shade_filter"])]
+    wait: bool,
+
+    /// How often to check the position of the shade when using --wait,
+    /// in milliseconds
+    #[arg(
+        long,
+        default_value = "500",
+        requires = "wait",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    poll_interval_ms: u64,
+
+    /// How many seconds to wait for the shade when using --wait
+    #[arg(long, default_value = "60", value_parser = crate::parse_duration, requires = "wait")]
+    timeout: Duration,
 }
 
 impl MoveShadeCommand {
     pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
         let hub = args.hub().await?;
 
-        let shade = hub.shade_by_name(&self.name).await?;
+        if let Some(room) = &self.room {
+            let room = hub.room_by_name(room).await?;
+            let shades = hub.list_shades(None, Some(room.id)).await?;
+            let failed = self.move_each(&hub, primary_rails(shades)).await;
+            if failed > 0 {
+                anyhow::bail!("{failed} shade(s) in room {} could not be moved", room.name);
+            }
+            return Ok(());
+        }
 
-        let shade = if let Some(motion) = self.target_position.motion {
-            hub.move_shade(shade.id, motion).await?
-        } else if let Some(percent) = self.target_position.percent {
-            let absolute = ShadePosition::percent_to_pos(percent);
+        if let Some(group_id) = self.group {
+            let shades = hub.list_shades(Some(group_id), None).await?;
+            if shades.is_empty() {
+                anyhow::bail!("No shades found in group {group_id}");
+            }
+            let failed = self.move_each(&hub, primary_rails(shades)).await;
+            if failed > 0 {
+                anyhow::bail!("{failed} shade(s) in group {group_id} could not be moved");
+            }
+            return Ok(());
+        }
 
-            let mut position = shade.positions.clone().ok_or_else(|| {
-                anyhow::anyhow!("shade has no existing position information! {shade:#?}")
-            })?;
-            if shade.is_primary() {
-                position.position_1 = absolute;
+        if let Some(pattern) = &self.shade_filter {
+            let shades = hub.shades_matching(pattern).await?;
+            let failed = self.move_each(&hub, shades).await;
+            if failed > 0 {
+                anyhow::bail!("{failed} shade(s) matching {pattern} could not be moved");
+            }
+            return Ok(());
+        }
+
+        let name = self
+            .name
+            .as_deref()
+            .expect("clap requires name, room, group or shade_filter");
+        let shade = hub.shade_by_id_or_name(name).await?;
+        let is_primary = shade.is_primary();
+
+        // Figure this out before moving, so that an unsupported
+        // combination of options doesn't leave the shade moving
+        let target = if self.wait {
+            Some(self.target_percent(&shade, is_primary)?)
+        } else {
+            None
+        };
+
+        let moved = self.move_one(&hub, &shade, is_primary).await?;
+
+        let Some(target) = target else {
+            println!("{moved:#?}");
+            return Ok(());
+        };
+
+        let deadline = Instant::now() + self.timeout;
+        let interval = Duration::from_millis(self.poll_interval_ms);
+        loop {
+            let shade = hub.shade_by_id(shade.id).await?;
+            let current = if is_primary {
+                shade.pos1_percent()
             } else {
-                position.position_2.replace(absolute);
+                shade.pos2_percent()
+            };
+            if let Some(current) = current {
+                // Allow for the hub reporting a slightly different
+                // position from the one we requested
+                if current.abs_diff(target) <= 1 {
+                    println!("{}: reached {current}%", shade.name());
+                    return Ok(());
+                }
+            }
+
+            if Instant::now() >= deadline {
+                let current = current
+                    .map(|pct| format!("{pct}%"))
+                    .unwrap_or_else(|| "unknown".to_string());
+                anyhow::bail!(
+                    "{} did not reach {target}% within {:?}; it is at {current}",
+                    shade.name(),
+                    self.timeout
+                );
             }
+            tokio::time::sleep(interval).await;
+        }
+    }
 
-            hub.change_shade_position(shade.id, position).await?
+    /// Returns the position, in percent, that the shade will be
+    /// moving to, for use with --wait
+    fn target_percent(&self, shade: &ShadeData, is_primary: bool) -> anyhow::Result<u8> {
+        let target = if let Some(percent) = self.target_position.percent {
+            Some(percent.min(100))
+        } else if self.target_position.favorite {
+            shade.favorite.as_ref().and_then(|favorite| {
+                if is_primary {
+                    Some(favorite.pos1_percent())
+                } else {
+                    favorite.pos2_percent()
+                }
+            })
         } else {
-            anyhow::bail!("One of --motion or --percent is required");
+            match self.target_position.motion {
+                Some(ShadeUpdateMotion::Up) => Some(100),
+                Some(ShadeUpdateMotion::Down) => Some(0),
+                _ => anyhow::bail!("--wait requires --percent, --favorite, or --motion up or down"),
+            }
         };
+        target.ok_or_else(|| {
+            anyhow::anyhow!(
+                "--wait cannot be used because the favorite position of {} is unknown",
+                shade.name()
+            )
+        })
+    }
 
-        println!("{shade:#?}");
-        Ok(())
+    /// Moves each of `shades` in turn, printing the outcome for each.
+    /// Returns the number of shades that could not be moved.
+    async fn move_each(&self, hub: &Hub, shades: Vec<ResolvedShadeData>) -> usize {
+        let mut failed = 0;
+        for shade in shades {
+            match self.move_one(hub, &shade, shade.is_primary()).await {
+                Ok(shade) => {
+                    let position = shade
+                        .positions
+                        .as_ref()
+                        .map(|p| p.describe())
+                        .unwrap_or_else(|| "unknown position".to_string());
+                    println!("{}: {position}", shade.name());
+                }
+                Err(err) => {
+                    println!("{}: failed: {err:#}", shade.name());
+                    failed += 1;
+                }
+            }
+        }
+        failed
     }
+
+    async fn move_one(
+        &self,
+        hub: &Hub,
+        shade: &ShadeData,
+        is_primary: bool,
+    ) -> anyhow::Result<ShadeData> {
+        if self.target_position.favorite {
+            hub.move_shade_to_favorite(shade.id).await
+        } else if let Some(motion) = self.target_position.motion {
+            hub.move_shade(shade.id, motion).await
+        } else if let Some(percent) = self.target_position.percent {
+            let mut position = shade.positions.clone().ok_or_else(|| {
+                anyhow::anyhow!("shade has no existing position information! {shade:#?}")
+            })?;
+            position.set_rail_percent(is_primary, percent);
+
+            hub.change_shade_position(shade.id, position).await
+        } else {
+            anyhow::bail!("One of --motion, --percent or --favorite is required");
+        }
+    }
+}
+
+/// Shades selected by room or group are moved by their primary rail
+fn primary_rails(shades: Vec<ShadeData>) -> Vec<ResolvedShadeData> {
+    shades.into_iter().map(ResolvedShadeData::Primary).collect()
 }
@@ -0,0 +1,69 @@
+use std::time::{Duration, Instant};
+
+/// The longest we'll wait between checks on the hub after rebooting it
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Restart the hub. This can help when the hub has become wedged,
+/// for example, if it keeps reporting stale data or refusing requests.
+#[derive(clap::Parser, Debug)]
+pub struct RebootHubCommand {
+    /// Don't prompt for confirmation before rebooting
+    #[arg(long)]
+    yes: bool,
+
+    /// Don't wait for the hub to start responding again
+    #[arg(long)]
+    no_wait: bool,
+
+    /// How many seconds to wait for the hub to respond after rebooting
+    #[arg(long, default_value = "300", value_parser = crate::parse_duration)]
+    timeout: Duration,
+}
+
+impl RebootHubCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let user_data = hub.get_user_data().await?;
+
+        println!("{user_data}");
+        println!("Shades will not respond to the hub until it has restarted.");
+        if !self.yes && !super::confirm("Reboot this hub?")? {
+            println!("Not rebooting the hub");
+            return Ok(());
+        }
+
+        hub.reboot().await?;
+        let start = Instant::now();
+        println!("Reboot requested");
+
+        if self.no_wait {
+            return Ok(());
+        }
+
+        // Give the hub a chance to actually go down, so that we
+        // don't mistake it for having already come back up
+        let mut interval = Duration::from_secs(5);
+        tokio::time::sleep(interval).await;
+
+        loop {
+            match hub.get_user_data().await {
+                Ok(user_data) => {
+                    println!(
+                        "Hub is responding again after {:?}: {user_data}",
+                        start.elapsed()
+                    );
+                    return Ok(());
+                }
+                Err(err) => {
+                    log::debug!("hub is not responding yet: {err:#}");
+                }
+            }
+
+            if start.elapsed() >= self.timeout {
+                anyhow::bail!("hub did not respond within {:?} of rebooting", self.timeout);
+            }
+            tokio::time::sleep(interval).await;
+            interval = (interval * 2).min(MAX_POLL_INTERVAL);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::api_types::{BatteryStatus, ShadeData};
+use crate::hub::Hub;
+use std::collections::HashMap;
+use std::time::Duration;
+use tabout::{Alignment, Column};
+
+/// Ask the hub to re-measure the battery level of a shade, or of all shades.
+/// Measuring the battery wakes up the motor, so avoid doing this too often.
+#[derive(clap::Parser, Debug)]
+pub struct RefreshBatteryCommand {
+    /// The name or id of the shade to refresh
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    name: Option<String>,
+
+    /// Refresh the battery level of every shade
+    #[arg(long)]
+    all: bool,
+
+    /// When using --all, how many seconds to wait between shades
+    #[arg(long, default_value = "2", value_parser = crate::parse_duration)]
+    delay: Duration,
+
+    /// When using --all, ask the hub to refresh every shade in a single
+    /// request rather than staggering the requests. If the hub doesn't
+    /// support that, each shade is refreshed in turn without any delay.
+    #[arg(long, requires = "all")]
+    bulk: bool,
+}
+
+enum Outcome {
+    Refreshed(ShadeData),
+    /// The request succeeded, but the hub has no battery information
+    Unavailable,
+    Failed(anyhow::Error),
+}
+
+impl Outcome {
+    /// Produces a key that sorts the shades that most need
+    /// attention before the healthy ones
+    fn sort_key(&self) -> (u8, u8) {
+        match self {
+            Self::Failed(_) => (0, 0),
+            Self::Unavailable => (1, 0),
+            Self::Refreshed(shade) if shade.battery_status == BatteryStatus::PluggedIn => (3, 0),
+            Self::Refreshed(shade) => (2, shade.battery_percent().unwrap_or(0)),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Refreshed(shade) => format!(
+                "{}% ({:?})",
+                shade.battery_percent().unwrap_or(0),
+                shade.battery_status
+            ),
+            Self::Unavailable => "hub reports battery level is unavailable".to_string(),
+            Self::Failed(err) => format!("request failed: {err:#}"),
+        }
+    }
+}
+
+impl Outcome {
+    fn from_shade(shade: ShadeData) -> Self {
+        if shade.battery_status == BatteryStatus::Unavailable {
+            Self::Unavailable
+        } else {
+            Self::Refreshed(shade)
+        }
+    }
+}
+
+impl RefreshBatteryCommand {
+    async fn refresh_staggered(&self, hub: &Hub) -> anyhow::Result<Vec<(String, Outcome)>> {
+        let shades: Vec<(i32, String)> = match &self.name {
+            Some(name) => {
+                let shade = hub.shade_by_id_or_name(name).await?;
+                vec![(shade.id, shade.name().to_string())]
+            }
+            None => hub
+                .list_shades(None, None)
+                .await?
+                .into_iter()
+                .map(|shade| (shade.id, shade.name().to_string()))
+                .collect(),
+        };
+
+        let mut results = vec![];
+        for (idx, (shade_id, name)) in shades.into_iter().enumerate() {
+            if idx > 0 {
+                tokio::time::sleep(self.delay).await;
+            }
+            let outcome = match hub.shade_update_battery_level(shade_id).await {
+                Ok(shade) => Outcome::from_shade(shade),
+                Err(err) => Outcome::Failed(err),
+            };
+            println!("{name}: {}", outcome.describe());
+            results.push((name, outcome));
+        }
+        Ok(results)
+    }
+
+    async fn refresh_bulk(&self, hub: &Hub) -> anyhow::Result<Vec<(String, Outcome)>> {
+        let shades = hub.list_shades(None, None).await?;
+        let mut refreshed: HashMap<i32, ShadeData> = hub
+            .get_all_shades_battery_status()
+            .await?
+            .into_iter()
+            .map(|shade| (shade.id, shade))
+            .collect();
+
+        let mut results = vec![];
+        for shade in shades {
+            let name = shade.name().to_string();
+            let outcome = match refreshed.remove(&shade.id) {
+                Some(shade) => Outcome::from_shade(shade),
+                None => Outcome::Failed(anyhow::anyhow!("the hub didn't report this shade")),
+            };
+            println!("{name}: {}", outcome.describe());
+            results.push((name, outcome));
+        }
+        Ok(results)
+    }
+
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let mut results = if self.bulk {
+            self.refresh_bulk(&hub).await?
+        } else {
+            self.refresh_staggered(&hub).await?
+        };
+
+        let failed = results
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, Outcome::Failed(_)))
+            .count();
+
+        if self.all {
+            results.sort_by(|(a_name, a), (b_name, b)| {
+                a.sort_key()
+                    .cmp(&b.sort_key())
+                    .then_with(|| a_name.cmp(b_name))
+            });
+
+            let columns = &[
+                Column {
+                    name: "SHADE".to_string(),
+                    alignment: Alignment::Left,
+                },
+                Column {
+                    name: "BATTERY".to_string(),
+                    alignment: Alignment::Right,
+                },
+                Column {
+                    name: "STATUS".to_string(),
+                    alignment: Alignment::Left,
+                },
+            ];
+            let rows: Vec<_> = results
+                .iter()
+                .map(|(name, outcome)| {
+                    let (battery, status) = match outcome {
+                        Outcome::Refreshed(shade) => (
+                            format!("{}%", shade.battery_percent().unwrap_or(0)),
+                            format!("{:?}", shade.battery_status),
+                        ),
+                        Outcome::Unavailable => (String::new(), "Unavailable".to_string()),
+                        Outcome::Failed(_) => (String::new(), "Request Failed".to_string()),
+                    };
+                    vec![name.to_string(), battery, status]
+                })
+                .collect();
+
+            println!();
+            println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+        }
+
+        if failed > 0 {
+            anyhow::bail!("{failed} shade(s) could not be refreshed");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use crate::api_types::ShadeData;
+use std::time::Duration;
+
+/// Ask the hub to query the current position of a shade, or of all the
+/// shades in a room, and show how the reported positions changed.
+/// This is useful when the position shown by the hub has drifted
+/// out of sync with the actual shade.
+#[derive(clap::Parser, Debug)]
+pub struct RefreshPositionCommand {
+    /// The name or id of the shade to refresh
+    #[arg(required_unless_present = "room", conflicts_with = "room")]
+    name: Option<String>,
+
+    /// Refresh every shade in the room with this name or id
+    #[arg(long)]
+    room: Option<String>,
+
+    /// When using --room, how many seconds to wait between shades
+    #[arg(long, default_value = "2", value_parser = crate::parse_duration)]
+    delay: Duration,
+}
+
+fn format_percent(pct: Option<u8>) -> String {
+    pct.map(|p| format!("{p}%"))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn describe_change(label: &str, before: Option<u8>, after: Option<u8>) -> String {
+    if before == after {
+        format!("{label} {} (unchanged)", format_percent(after))
+    } else {
+        format!(
+            "{label} {} -> {}",
+            format_percent(before),
+            format_percent(after)
+        )
+    }
+}
+
+impl RefreshPositionCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let shades: Vec<ShadeData> = match (&self.name, &self.room) {
+            (Some(name), _) => {
+                let shade = hub.shade_by_id_or_name(name).await?;
+                vec![hub.shade_by_id(shade.id).await?]
+            }
+            (None, Some(room)) => {
+                let room = hub.room_by_name(room).await?;
+                hub.list_shades(None, Some(room.id)).await?
+            }
+            (None, None) => unreachable!("clap requires either name or --room"),
+        };
+
+        let mut failed = 0;
+        for (idx, before) in shades.iter().enumerate() {
+            if idx > 0 {
+                tokio::time::sleep(self.delay).await;
+            }
+
+            // shade_refresh_position fails if the hub reports that the
+            // shade timed out, so we never show stale positions here
+            match hub.shade_refresh_position(before.id).await {
+                Ok(after) => {
+                    let mut changes = vec![describe_change(
+                        "primary",
+                        before.pos1_percent(),
+                        after.pos1_percent(),
+                    )];
+                    if before.pos2_percent().is_some() || after.pos2_percent().is_some() {
+                        changes.push(describe_change(
+                            "secondary",
+                            before.pos2_percent(),
+                            after.pos2_percent(),
+                        ));
+                    }
+                    println!("{}: {}", before.name(), changes.join(", "));
+                }
+                Err(err) => {
+                    println!("{}: failed: {err:#}", before.name());
+                    failed += 1;
+                }
+            }
+        }
+
+        if failed > 0 {
+            anyhow::bail!("{failed} shade(s) could not be refreshed");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+/// Remove a shade from a scene
+#[derive(clap::Parser, Debug)]
+pub struct RemoveShadeFromSceneCommand {
+    /// The name or id of the scene.
+    /// Names will be compared ignoring case.
+    scene: String,
+
+    /// The name or id of the shade to remove from the scene.
+    /// Names will be compared ignoring case.
+    #[arg(required_unless_present = "all")]
+    shade: Option<String>,
+
+    /// Remove every shade from the scene
+    #[arg(long, conflicts_with = "shade")]
+    all: bool,
+}
+
+impl RemoveShadeFromSceneCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let scene = hub.scene_by_name(&self.scene).await?;
+        let members = hub
+            .list_scene_members()
+            .await?
+            .remove(&scene.id)
+            .unwrap_or_default();
+
+        let members = match &self.shade {
+            Some(name) => {
+                let shade = hub.shade_by_id_or_name(name).await?;
+                let members: Vec<_> = members
+                    .into_iter()
+                    .filter(|member| member.shade_id == shade.id)
+                    .collect();
+                if members.is_empty() {
+                    anyhow::bail!(
+                        "Shade {} {} is not a member of scene {} {}",
+                        shade.id,
+                        shade.name(),
+                        scene.id,
+                        scene.name
+                    );
+                }
+                members
+            }
+            None => members,
+        };
+
+        for member in &members {
+            hub.delete_scene_member(member.id).await?;
+            println!(
+                "Removed shade {} from scene {} {}",
+                member.shade_id, scene.id, scene.name
+            );
+        }
+        Ok(())
+    }
+}
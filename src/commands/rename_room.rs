@@ -0,0 +1,42 @@
+use super::list_rooms::JsonRoom;
+
+/// Change the name of a room
+#[derive(clap::Parser, Debug)]
+pub struct RenameRoomCommand {
+    /// The current name or id of the room.
+    /// Names will be compared ignoring case.
+    name: String,
+
+    /// The new name for the room
+    #[arg(long)]
+    to: String,
+
+    /// Print the updated room as json
+    #[arg(long)]
+    json: bool,
+}
+
+impl RenameRoomCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let new_name = self.to.trim();
+        if new_name.is_empty() {
+            anyhow::bail!("The new room name must not be empty");
+        }
+
+        let hub = args.hub().await?;
+
+        let room = hub.room_by_name(&self.name).await?;
+        let updated = hub.rename_room(room.id, new_name).await?;
+
+        if self.json {
+            let room = JsonRoom::new(&updated, None);
+            println!("{}", serde_json::to_string_pretty(&room)?);
+        } else {
+            println!(
+                "Renamed room {}: {} -> {}",
+                room.id, room.name, updated.name
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+/// Change the name of a shade
+#[derive(clap::Parser, Debug)]
+pub struct RenameShadeCommand {
+    /// The current name or id of the shade.
+    /// Names will be compared ignoring case.
+    name: String,
+
+    /// The new name for the shade
+    #[arg(required_unless_present = "to", conflicts_with = "to")]
+    new_name: Option<String>,
+
+    /// The new name for the shade; an alternative to
+    /// passing it as the second positional argument
+    #[arg(long)]
+    to: Option<String>,
+
+    /// Change the secondary name of the shade, rather than its name.
+    /// The secondary name labels the middle rail of top-down/bottom-up
+    /// shades.
+    #[arg(long)]
+    secondary: bool,
+}
+
+impl RenameShadeCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let new_name = self
+            .new_name
+            .as_deref()
+            .or(self.to.as_deref())
+            .unwrap_or_default()
+            .trim();
+        if new_name.is_empty() {
+            anyhow::bail!("The new shade name must not be empty");
+        }
+
+        let hub = args.hub().await?;
+
+        let shade = hub.shade_by_id_or_name(&self.name).await?;
+        let updated = if self.secondary {
+            let updated = hub.rename_shade_secondary(shade.id, new_name).await?;
+            println!(
+                "Renamed secondary name of shade {}: {} -> {}",
+                shade.id,
+                shade.secondary_name(),
+                updated.secondary_name()
+            );
+            updated
+        } else {
+            let updated = hub.rename_shade(shade.id, new_name).await?;
+            println!(
+                "Renamed shade {}: {} -> {}",
+                shade.id,
+                shade.name(),
+                updated.name()
+            );
+            updated
+        };
+        println!("{updated:#?}");
+        Ok(())
+    }
+}
@@ -0,0 +1,26 @@
+/// Change the display order of a scene
+#[derive(clap::Parser, Debug)]
+pub struct ReorderSceneCommand {
+    /// The name or id of the scene.
+    /// Names will be compared ignoring case.
+    name: String,
+
+    /// The new position of the scene in the display order
+    #[arg(long)]
+    order: i32,
+}
+
+impl ReorderSceneCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let scene = hub.scene_by_name(&self.name).await?;
+        let updated = hub.change_scene_order(scene.id, self.order).await?;
+
+        println!(
+            "Scene {} {}: order {} -> {}",
+            scene.id, scene.name, scene.order, updated.order
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+/// Change the display order of a shade
+#[derive(clap::Parser, Debug)]
+pub struct ReorderShadeCommand {
+    /// The name or id of the shade.
+    /// Names will be compared ignoring case.
+    #[arg(long)]
+    shade: String,
+
+    /// The new position of the shade in the display order
+    #[arg(long)]
+    order: i32,
+}
+
+impl ReorderShadeCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let shade = hub.shade_by_id_or_name(&self.shade).await?;
+        let updated = hub.change_shade_order(shade.id, self.order).await?;
+
+        let describe = |order: Option<i32>| match order {
+            Some(order) => order.to_string(),
+            None => "-".to_string(),
+        };
+        println!(
+            "Shade {} {}: order {} -> {}",
+            shade.id,
+            shade.name(),
+            describe(shade.order),
+            describe(updated.order)
+        );
+        Ok(())
+    }
+}
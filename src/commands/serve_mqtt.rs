@@ -1,34 +1,45 @@
 use crate::api_types::{
-    HomeAutomationPostBackData, HomeAutomationRecordType, HomeAutomationService, ShadeBatteryKind,
-    ShadeCapabilityFlags, ShadeData, ShadePosition, ShadeUpdateMotion, UserData,
+    BatteryStatus, HomeAutomationPostBackData, HomeAutomationRecordType, HomeAutomationService,
+    ShadeBatteryKind, ShadeCapabilityFlags, ShadeData, ShadeUpdateMotion, UserData,
 };
 use crate::discovery::ResolvedHub;
 use crate::hass_helper::*;
-use crate::http_helpers::LockedError;
+use crate::http_helpers::HubError;
 use crate::hub::Hub;
+use crate::metrics::{metrics_router, Metrics};
 use crate::opt_env_var;
+use crate::postback::{postback_url, start_postback_server_with_routes, PostbackEvent};
+use crate::schedule::{ScheduleEntry, ScheduleFile};
 use crate::version_info::pview_version;
 use anyhow::Context;
 use arc_swap::ArcSwap;
-use axum::extract::Path;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use handlebars::Handlebars;
 use mosquitto_rs::router::*;
 use mosquitto_rs::*;
-use serde::Deserialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
 use std::fmt::Debug;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc::{Receiver, Sender};
+use tokio_cron_scheduler::{Job, JobScheduler};
 
 const SECONDARY_SUFFIX: &str = "_middle";
+const TILT_SUFFIX: &str = "_tilt";
 const MODEL: &str = "pv2mqtt";
 const WEZ: &str = "Wez Furlong";
 const HUNTER_DOUGLAS: &str = "Hunter Douglas";
-const BATTERY_LABEL: &str = "Battery";
-const RECHARGEABLE_LABEL: &str = "Rechargeable Battery";
-const HARD_WIRED_LABEL: &str = "Hard Wired";
+/// Requests to re-register with hass that arrive within this
+/// period of the prior registration are coalesced into it
+const REGISTRATION_DEBOUNCE: Duration = Duration::from_secs(5);
+/// How long to wait after the hub reports a configuration change
+/// before re-registering, so that a burst of changes is coalesced
+const CONFIG_CHANGE_DEBOUNCE: Duration = Duration::from_secs(10);
 
 // <https://www.home-assistant.io/integrations/cover.mqtt/>
 
@@ -55,11 +66,200 @@ pub struct ServeMqttCommand {
     #[arg(long)]
     password: Option<String>,
 
+    /// The keepalive interval for the connection to the broker, in seconds.
+    /// You may also set this via the PV_MQTT_KEEPALIVE environment variable.
+    /// If unspecified, uses 10
+    #[arg(long)]
+    mqtt_keepalive: Option<u64>,
+
+    /// How many seconds to wait before trying to reconnect to the
+    /// broker after the connection is lost
+    #[arg(long, default_value = "5", value_parser = crate::parse_duration)]
+    reconnect_delay: Duration,
+
+    /// Give up and exit after this many consecutive failed attempts
+    /// to reconnect to the broker. The default is to keep trying
+    /// indefinitely.
+    #[arg(long)]
+    max_reconnect_attempts: Option<usize>,
+
+    /// The topic used to advertise whether the bridge itself is
+    /// connected. `online` is published when we connect, and the
+    /// broker publishes `offline` on our behalf, as our last will,
+    /// if we disconnect unexpectedly.
+    /// The default is pv2mqtt/bridge/<hub serial>/availability
+    #[arg(long)]
+    bridge_availability_topic: Option<String>,
+
     #[arg(long)]
     bind_address: Option<String>,
 
     #[arg(long, default_value = "homeassistant")]
     discovery_prefix: String,
+
+    /// Publish the discovery configs with the retain flag set, so that
+    /// Home Assistant can recreate the entities after it restarts,
+    /// without waiting for us to publish them again.
+    /// Use --retain-config=false to disable this.
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    retain_config: bool,
+
+    /// When a shade is commanded to move via mqtt, immediately publish
+    /// its target position and open/closed state, rather than waiting
+    /// for the hub to report the new position. The next postback or
+    /// periodic update will confirm or correct it.
+    #[arg(long)]
+    state_update_on_command: bool,
+
+    /// The port on which to run the http server that receives
+    /// postbacks from the hub. The default of 0 picks an unused port.
+    #[arg(long, default_value = "0")]
+    http_port: u16,
+
+    /// Serve Prometheus metrics describing the bridge and the shades
+    /// at /metrics on the http server. Use --http-port to make the
+    /// server listen on a predictable port.
+    #[arg(long)]
+    metrics: bool,
+
+    /// While a shade is moving, periodically publish an estimate of
+    /// its position, based on the travel time reported by the hub,
+    /// so that Home Assistant shows its progress rather than jumping
+    /// from the start to the end position.
+    #[arg(long)]
+    estimate_motion: bool,
+
+    /// Tell Home Assistant to assume that shades of these types reach
+    /// the position that they were commanded to move to, rather than
+    /// waiting for them to report it. This can help with shades whose
+    /// position reports are unreliable. The types are the numeric
+    /// shadeType values reported by `inspect-shade --raw`, separated
+    /// by commas.
+    #[arg(long, value_delimiter = ',')]
+    optimistic_shade_types: Vec<i32>,
+
+    /// Log the messages that would be published to the broker,
+    /// rather than publishing them, and don't register the automation
+    /// hook with the hub. Commands from Home Assistant and scheduled
+    /// moves are ignored, so the hub is not changed in any way.
+    /// This is useful for checking the entities that would be created
+    /// in Home Assistant.
+    #[arg(long)]
+    dry_run: bool,
+
+    /// The maximum number of requests to have in flight to the hub
+    /// at once. Requests beyond this limit wait their turn, so that
+    /// a burst of commands from Home Assistant doesn't overwhelm
+    /// the hub.
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u16).range(1..))]
+    hub_concurrency: u16,
+
+    /// A TOML file describing shade movements that should be made
+    /// on a schedule, using cron syntax:
+    ///
+    /// [[schedule]]
+    /// cron = "0 7 * * *"
+    /// shade = "Bedroom"
+    /// percent = 0
+    #[arg(long)]
+    schedule_file: Option<PathBuf>,
+
+    /// Round the positions reported to Home Assistant to the nearest
+    /// multiple of this many percent, to reduce noise in the reported
+    /// positions.
+    #[arg(
+        long,
+        default_value = "1",
+        value_parser = PossibleValuesParser::new(["1", "2", "5", "10"])
+            .map(|s| s.parse::<u8>().expect("possible values are numeric"))
+    )]
+    position_precision: u8,
+
+    /// Use the address of the named network interface (eg: eth0) as
+    /// the address that the hub should use to post events back to us,
+    /// rather than the address that the kernel chooses for connecting
+    /// to the hub. Useful on multi-homed hosts.
+    #[arg(long)]
+    preferred_network_interface: Option<String>,
+
+    /// Poll the hub for the position of all shades every N seconds,
+    /// and report them to Home Assistant. This is a fallback for
+    /// situations where the hub isn't able to post events back to us.
+    #[arg(long, value_parser = crate::parse_nonzero_duration)]
+    poll_interval: Option<Duration>,
+
+    /// Ignore requests to activate a scene that arrive within this
+    /// many milliseconds of a prior successful activation of the
+    /// same scene. The default of 0 disables this.
+    #[arg(long, default_value = "0")]
+    scene_activation_debounce_ms: u64,
+
+    /// A Handlebars template used to produce the name of each shade
+    /// entity in Home Assistant, for example `{{room}} - {{shade}}`.
+    /// The available variables are `room`, `shade` and `position`,
+    /// where position is empty for the primary rail and `Middle Rail`
+    /// for the secondary rail.  When not specified, the primary rail
+    /// entity takes the name of the shade.
+    #[arg(long, value_parser = parse_entity_name_template)]
+    entity_name_template: Option<EntityNameTemplate>,
+}
+
+const ENTITY_NAME_TEMPLATE: &str = "entity_name";
+
+/// A compiled --entity-name-template
+#[derive(Clone, Debug)]
+struct EntityNameTemplate(Arc<Handlebars<'static>>);
+
+#[derive(Serialize)]
+struct EntityNameContext<'a> {
+    room: &'a str,
+    shade: &'a str,
+    position: &'a str,
+}
+
+impl EntityNameTemplate {
+    fn render(&self, room: &str, shade: &str, position: &str) -> anyhow::Result<String> {
+        let name = self.0.render(
+            ENTITY_NAME_TEMPLATE,
+            &EntityNameContext {
+                room,
+                shade,
+                position,
+            },
+        )?;
+        Ok(name.trim().to_string())
+    }
+}
+
+fn parse_entity_name_template(template: &str) -> anyhow::Result<EntityNameTemplate> {
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    // The names are not html, so don't escape them
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_template_string(ENTITY_NAME_TEMPLATE, template)?;
+
+    let template = EntityNameTemplate(Arc::new(handlebars));
+    // Validate that only the supported variables are referenced
+    template.render("Room", "Shade", "Middle Rail")?;
+    Ok(template)
+}
+
+/// Returns the address assigned to the named network interface,
+/// preferring an IPv4 address over an IPv6 address
+fn interface_address(name: &str) -> anyhow::Result<IpAddr> {
+    let addrs: Vec<IpAddr> = if_addrs::get_if_addrs()
+        .context("listing network interfaces")?
+        .into_iter()
+        .filter(|iface| iface.name == name)
+        .map(|iface| iface.ip())
+        .collect();
+
+    addrs
+        .iter()
+        .find(|addr| addr.is_ipv4())
+        .or_else(|| addrs.first())
+        .copied()
+        .ok_or_else(|| anyhow::anyhow!("No address found for network interface {name}"))
 }
 
 enum ServerEvent {
@@ -67,18 +267,54 @@ enum ServerEvent {
         router: Arc<MqttRouter<Arc<Pv2MqttState>>>,
         msg: Message,
     },
-    HomeAutomationData {
-        serial: String,
-        data: Vec<HomeAutomationPostBackData>,
-    },
+    HomeAutomationData(PostbackEvent),
     PeriodicStateUpdate,
-    HubDiscovered(ResolvedHub),
+    PollShadePositions,
+    HubDiscovered(Box<ResolvedHub>),
+    ScheduledMove(ScheduleEntry),
+    /// The hub configuration changed to this config number,
+    /// and has since settled down
+    ConfigChanged(i64),
+    Shutdown,
 }
 
-#[derive(Debug)]
 enum RegEntry {
     Delay(Duration),
-    Msg { topic: String, payload: String },
+    Msg {
+        topic: String,
+        payload: String,
+    },
+    /// Publish the payload only if the condition holds
+    /// at the time that the registration is applied
+    Conditional {
+        topic: String,
+        payload: String,
+        condition: Box<dyn Fn() -> bool + Send + Sync>,
+    },
+}
+
+impl Debug for RegEntry {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Delay(duration) => fmt.debug_tuple("Delay").field(duration).finish(),
+            Self::Msg { topic, payload } => fmt
+                .debug_struct("Msg")
+                .field("topic", topic)
+                .field("payload", payload)
+                .finish(),
+            Self::Conditional { topic, payload, .. } => fmt
+                .debug_struct("Conditional")
+                .field("topic", topic)
+                .field("payload", payload)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl From<PostbackEvent> for ServerEvent {
+    fn from(event: PostbackEvent) -> Self {
+        Self::HomeAutomationData(event)
+    }
 }
 
 impl RegEntry {
@@ -88,6 +324,38 @@ impl RegEntry {
             payload: payload.into(),
         }
     }
+
+    pub async fn apply(self, state: &Arc<Pv2MqttState>, retain: bool) -> anyhow::Result<()> {
+        let (topic, payload) = match self {
+            Self::Delay(duration) => {
+                tokio::time::sleep(duration).await;
+                return Ok(());
+            }
+            Self::Msg { topic, payload } => (topic, payload),
+            Self::Conditional {
+                topic,
+                payload,
+                condition,
+            } => {
+                if !condition() {
+                    return Ok(());
+                }
+                (topic, payload)
+            }
+        };
+
+        if topic.ends_with("/availability") {
+            state
+                .availability_topics
+                .lock()
+                .unwrap()
+                .insert(topic.clone());
+        }
+        state
+            .publish(&topic, payload.as_bytes(), QoS::AtMostOnce, retain)
+            .await?;
+        Ok(())
+    }
 }
 
 struct HassRegistration {
@@ -120,6 +388,19 @@ impl HassRegistration {
         self.updates.push(RegEntry::msg(topic, payload));
     }
 
+    pub fn update_if<T: Into<String>, P: Into<String>>(
+        &mut self,
+        topic: T,
+        payload: P,
+        condition: impl Fn() -> bool + Send + Sync + 'static,
+    ) {
+        self.updates.push(RegEntry::Conditional {
+            topic: topic.into(),
+            payload: payload.into(),
+            condition: Box::new(condition),
+        });
+    }
+
     pub async fn apply_updates(mut self, state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
         let is_first_run = state.first_run.load(Ordering::SeqCst);
 
@@ -139,19 +420,41 @@ impl HassRegistration {
             }
         } else {
             self.deletes.clear();
+
+            // hass already knows about configs that haven't changed
+            // since we last sent them
+            let last_configs = state.last_config_payloads.lock().unwrap();
+            self.configs.retain(|entry| match entry {
+                RegEntry::Msg { topic, payload } => last_configs
+                    .get(topic)
+                    .map(|last| last != payload)
+                    .unwrap_or(true),
+                RegEntry::Delay(_) | RegEntry::Conditional { .. } => true,
+            });
         }
-        for queue in [self.deletes, self.configs, self.updates] {
+        // Deletes must be retained along with the configs, otherwise
+        // the broker would continue to serve up the retained config
+        let retain_config = state.retain_config;
+        for (queue, retain, is_config) in [
+            (self.deletes, retain_config, false),
+            (self.configs, retain_config, true),
+            (self.updates, false, false),
+        ] {
             for entry in queue {
-                match entry {
-                    RegEntry::Delay(duration) => {
-                        tokio::time::sleep(duration).await;
-                    }
-                    RegEntry::Msg { topic, payload } => {
-                        state
-                            .client
-                            .publish(&topic, payload.as_bytes(), QoS::AtMostOnce, false)
-                            .await?;
+                let sent_config = match (is_config, &entry) {
+                    (true, RegEntry::Msg { topic, payload }) => {
+                        Some((topic.clone(), payload.clone()))
                     }
+                    _ => None,
+                };
+                entry.apply(state, retain).await?;
+                // Only remember configs once hass has actually been sent them
+                if let Some((topic, payload)) = sent_config {
+                    state
+                        .last_config_payloads
+                        .lock()
+                        .unwrap()
+                        .insert(topic, payload);
                 }
             }
         }
@@ -179,6 +482,7 @@ async fn register_diagnostic_entity(
         base: EntityConfig {
             name: Some(diagnostic.name),
             availability_topic: format!("{MODEL}/sensor/{unique_id}/availability"),
+            availability: vec![],
             device: Device {
                 identifiers: vec![
                     format!("{MODEL}-{serial}"),
@@ -186,11 +490,7 @@ async fn register_diagnostic_entity(
                     user_data.mac_address.to_string(),
                 ],
                 connections: vec![("mac".to_string(), user_data.mac_address.to_string())],
-                name: format!(
-                    "{} PowerView Hub: {}",
-                    user_data.brand,
-                    user_data.hub_name.to_string()
-                ),
+                name: format!("{} PowerView Hub: {}", user_data.brand, user_data.hub_name),
                 manufacturer: WEZ.to_string(),
                 model: MODEL.to_string(),
                 sw_version: Some(pview_version().to_string()),
@@ -277,14 +577,8 @@ async fn register_shades(
     reg: &mut HassRegistration,
 ) -> anyhow::Result<()> {
     let hub = state.hub.load();
-    let shades = hub.hub.list_shades(None, None).await?;
-    let room_by_id: HashMap<_, _> = hub
-        .hub
-        .list_rooms()
-        .await?
-        .into_iter()
-        .map(|room| (room.id, room.name))
-        .collect();
+    let (shades, rooms) = tokio::try_join!(hub.hub.list_shades(None, None), hub.hub.list_rooms())?;
+    let room_by_id: HashMap<_, _> = rooms.into_iter().map(|room| (room.id, room.name)).collect();
 
     let serial = &state.serial;
 
@@ -318,36 +612,56 @@ async fn register_shades(
         let device_id = format!("{serial}-{}", shade.id);
         let device = Device {
             suggested_area: area,
-            identifiers: vec![device_id.clone()],
+            identifiers: std::iter::once(device_id.clone())
+                .chain(shade.serial_number.clone())
+                .collect(),
             via_device: Some(format!("{MODEL}-{serial}")),
             name: shade.name().to_string(),
             manufacturer: HUNTER_DOUGLAS.to_string(),
             model: MODEL.to_string(),
             connections: vec![],
-            sw_version: shade
-                .firmware
-                .as_ref()
-                .map(|vers| format!("{}.{}.{}", vers.revision, vers.sub_revision, vers.build)),
+            sw_version: shade.firmware.as_ref().map(|vers| vers.to_string()),
         };
 
         for (shade_id, shade_name, pos) in shades {
             let unique_id = format!("{serial}-{shade_id}");
+            let shade_name = match &state.entity_name_template {
+                Some(template) => Some(template.render(
+                    device.suggested_area.as_deref().unwrap_or(""),
+                    shade.name(),
+                    shade_name.as_deref().unwrap_or(""),
+                )?),
+                None => shade_name,
+            };
 
             let config = CoverConfig {
                 base: EntityConfig {
                     unique_id,
                     name: shade_name,
                     availability_topic: format!("{MODEL}/shade/{serial}/{shade_id}/availability"),
+                    availability: vec![state.bridge_availability_topic.clone()],
                     device_class: Some("shade".to_string()),
                     origin: Origin::default(),
                     device: device.clone(),
                     entity_category: None,
                     icon: None,
                 },
-                command_topic: format!("{MODEL}/shade/{serial}/{shade_id}/command"),
-                position_topic: format!("{MODEL}/shade/{serial}/{shade_id}/position"),
-                set_position_topic: format!("{MODEL}/shade/{serial}/{shade_id}/set_position"),
-                state_topic: format!("{MODEL}/shade/{serial}/{shade_id}/state"),
+                command_topic: Some(format!("{MODEL}/shade/{serial}/{shade_id}/command")),
+                position_topic: Some(format!("{MODEL}/shade/{serial}/{shade_id}/position")),
+                set_position_topic: Some(format!("{MODEL}/shade/{serial}/{shade_id}/set_position")),
+                state_topic: Some(format!("{MODEL}/shade/{serial}/{shade_id}/state")),
+                tilt_status_topic: None,
+                tilt_command_topic: None,
+                tilt_min: None,
+                tilt_max: None,
+                // These match the commands handled by mqtt_shade_command
+                payload_open: Some("OPEN".to_string()),
+                payload_close: Some("CLOSE".to_string()),
+                payload_stop: Some("STOP".to_string()),
+                optimistic: state
+                    .optimistic_shade_types
+                    .contains(&(shade.shade_type as i32))
+                    .then_some(true),
             };
 
             // Delete legacy version of this shade, for those upgrading.
@@ -373,6 +687,8 @@ async fn register_shades(
             // shade, I've seen the primary position reported, but the secondary
             // is blank
             if let Some(pos) = pos {
+                let pos = state.round_position(pos);
+                state.metrics.set_shade_position(&shade_id, pos);
                 reg.update(
                     format!("{MODEL}/shade/{serial}/{shade_id}/position"),
                     format!("{pos}"),
@@ -382,6 +698,52 @@ async fn register_shades(
             }
         }
 
+        if shade.can_tilt() {
+            // The vanes are represented as a separate cover entity
+            // that only has tilt controls
+            let shade_id = format!("{}{TILT_SUFFIX}", shade.id);
+            let config = CoverConfig {
+                base: EntityConfig {
+                    unique_id: format!("{serial}-{shade_id}"),
+                    name: Some("Tilt".to_string()),
+                    availability_topic: format!("{MODEL}/shade/{serial}/{shade_id}/availability"),
+                    availability: vec![state.bridge_availability_topic.clone()],
+                    device_class: Some("blind".to_string()),
+                    origin: Origin::default(),
+                    device: device.clone(),
+                    entity_category: None,
+                    icon: None,
+                },
+                command_topic: None,
+                position_topic: None,
+                set_position_topic: None,
+                state_topic: None,
+                tilt_status_topic: Some(format!("{MODEL}/shade/{serial}/{}/tilt", shade.id)),
+                tilt_command_topic: Some(format!("{MODEL}/shade/{serial}/{}/set_tilt", shade.id)),
+                tilt_min: Some(0),
+                tilt_max: Some(100),
+                payload_open: None,
+                payload_close: None,
+                payload_stop: None,
+                optimistic: None,
+            };
+
+            reg.config(
+                format!(
+                    "{}/cover/{serial}-{shade_id}/config",
+                    state.discovery_prefix
+                ),
+                serde_json::to_string(&config)?,
+            );
+            reg.update(config.base.availability_topic, "online");
+            if let Some(tilt) = shade.tilt_percent() {
+                reg.update(
+                    format!("{MODEL}/shade/{serial}/{}/tilt", shade.id),
+                    format!("{tilt}"),
+                );
+            }
+        }
+
         {
             let jog = ButtonConfig {
                 base: EntityConfig {
@@ -391,6 +753,7 @@ async fn register_shades(
                         "{MODEL}/shade/{serial}/{}/jog/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -423,6 +786,7 @@ async fn register_shades(
                         "{MODEL}/shade/{serial}/{}/calibrate/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -457,6 +821,7 @@ async fn register_shades(
                         "{MODEL}/shade/{serial}/{}/heart/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -484,14 +849,15 @@ async fn register_shades(
                 base: EntityConfig {
                     unique_id: format!("{device_id}-battery"),
                     name: Some("Battery".to_string()),
-                    availability_topic: state.battery_availability_topic(&shade),
+                    availability_topic: state.battery_availability_topic(shade),
+                    availability: vec![],
                     device_class: Some("battery".to_string()),
                     origin: Origin::default(),
                     device: device.clone(),
                     entity_category: Some("diagnostic".to_string()),
                     icon: None,
                 },
-                state_topic: state.battery_state_topic(&shade),
+                state_topic: state.battery_state_topic(shade),
                 unit_of_measurement: Some("%".to_string()),
             };
             reg.delete(format!(
@@ -507,11 +873,20 @@ async fn register_shades(
                 serde_json::to_string(&battery)?,
             );
 
+            let has_battery = shade.battery_status != BatteryStatus::Unavailable;
+            reg.update_if(
+                battery.base.availability_topic.clone(),
+                "online",
+                move || has_battery,
+            );
+            reg.update_if(battery.base.availability_topic, "offline", move || {
+                !has_battery
+            });
             if let Some(pct) = shade.battery_percent() {
-                reg.update(battery.base.availability_topic, "online");
+                state
+                    .metrics
+                    .set_battery_percent(&shade.id.to_string(), pct);
                 reg.update(battery.state_topic, format!("{pct}"));
-            } else {
-                reg.update(battery.base.availability_topic, "offline");
             }
         }
         {
@@ -523,6 +898,7 @@ async fn register_shades(
                         "{MODEL}/shade/{serial}/{}/rebattery/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -558,6 +934,7 @@ async fn register_shades(
                         "{MODEL}/sensor/{serial}/{}/signal/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -597,6 +974,7 @@ async fn register_shades(
                         "{MODEL}/shade/{serial}/{}/refresh/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -632,6 +1010,7 @@ async fn register_shades(
                         "{MODEL}/shade/{serial}/{}/psu/availability",
                         shade.id
                     ),
+                    availability: vec![],
                     device_class: None,
                     origin: Origin::default(),
                     device: device.clone(),
@@ -639,11 +1018,11 @@ async fn register_shades(
                     icon: Some("mdi:power-plug-outline".to_string()),
                 },
                 command_topic: format!("{MODEL}/shade/{serial}/{}/command", shade.id),
-                state_topic: state.battery_kind_state_topic(&shade),
+                state_topic: state.battery_kind_state_topic(shade),
                 options: vec![
-                    HARD_WIRED_LABEL.to_string(),
-                    BATTERY_LABEL.to_string(),
-                    RECHARGEABLE_LABEL.to_string(),
+                    ShadeBatteryKind::HARD_WIRED_LABEL.to_string(),
+                    ShadeBatteryKind::BATTERY_LABEL.to_string(),
+                    ShadeBatteryKind::RECHARGEABLE_LABEL.to_string(),
                 ],
             };
             reg.delete(format!(
@@ -659,7 +1038,7 @@ async fn register_shades(
             reg.update(power_source.base.availability_topic, "online");
             reg.update(
                 power_source.state_topic,
-                battery_kind_to_state(shade.battery_kind).to_string(),
+                shade.battery_kind.label().to_string(),
             );
         }
     }
@@ -672,14 +1051,8 @@ async fn register_scenes(
     reg: &mut HassRegistration,
 ) -> anyhow::Result<()> {
     let hub = state.hub.load();
-    let scenes = hub.hub.list_scenes().await?;
-    let room_by_id: HashMap<_, _> = hub
-        .hub
-        .list_rooms()
-        .await?
-        .into_iter()
-        .map(|room| (room.id, room.name))
-        .collect();
+    let (scenes, rooms) = tokio::try_join!(hub.hub.list_scenes(), hub.hub.list_rooms())?;
+    let room_by_id: HashMap<_, _> = rooms.into_iter().map(|room| (room.id, room.name)).collect();
 
     let serial = &state.serial;
 
@@ -704,6 +1077,7 @@ async fn register_scenes(
                     sw_version: None,
                 },
                 availability_topic: format!("{MODEL}/scene/{serial}/{scene_id}/availability"),
+                availability: vec![],
                 device_class: None,
                 name: None,
                 origin: Origin::default(),
@@ -733,8 +1107,122 @@ async fn register_scenes(
     Ok(())
 }
 
+async fn register_scene_collections(
+    state: &Arc<Pv2MqttState>,
+    reg: &mut HassRegistration,
+) -> anyhow::Result<()> {
+    let hub = state.hub.load();
+    let collections = hub.hub.list_scene_collections().await?;
+
+    let serial = &state.serial;
+
+    for collection in collections {
+        let collection_id = collection.id;
+        let unique_id = format!("{serial}-scene-collection-{collection_id}");
+
+        let config = SceneConfig {
+            base: EntityConfig {
+                device: Device {
+                    suggested_area: None,
+                    identifiers: vec![unique_id.clone()],
+                    via_device: Some(format!("{MODEL}-{serial}")),
+                    name: collection.name.to_string(),
+                    manufacturer: HUNTER_DOUGLAS.to_string(),
+                    model: MODEL.to_string(),
+                    connections: vec![],
+                    sw_version: None,
+                },
+                availability_topic: format!(
+                    "{MODEL}/scene_collection/{serial}/{collection_id}/availability"
+                ),
+                availability: vec![],
+                device_class: None,
+                name: None,
+                origin: Origin::default(),
+                unique_id: unique_id.clone(),
+                entity_category: None,
+                icon: None,
+            },
+            command_topic: format!("{MODEL}/scene_collection/{serial}/{collection_id}/set"),
+            payload_on: "ON".to_string(),
+        };
+
+        reg.config(
+            format!("{}/scene/{unique_id}/config", state.discovery_prefix),
+            serde_json::to_string(&config)?,
+        );
+
+        reg.update(config.base.availability_topic, "online");
+    }
+
+    Ok(())
+}
+
+/// Called when the hub advises us that its configuration has changed,
+/// for example because a shade was renamed or added.  Since a number
+/// of these can arrive in quick succession, we wait for things to
+/// settle down and then re-register only for the most recent change.
+fn schedule_config_change_registration(
+    state: &Arc<Pv2MqttState>,
+    tx: &Sender<ServerEvent>,
+    config_num: i64,
+) {
+    let prior = state.config_num.swap(config_num, Ordering::SeqCst);
+    if prior == config_num {
+        return;
+    }
+    log::info!("Hub configuration changed from {prior} to {config_num}");
+
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(CONFIG_CHANGE_DEBOUNCE).await;
+        // Route through the main loop, so that the registration is
+        // serialized with respect to the other registrations
+        if let Err(err) = tx.send(ServerEvent::ConfigChanged(config_num)).await {
+            log::error!("config change: send to main thread: {err:#}");
+        }
+    });
+}
+
+/// Register with hass, unless we already did so very recently.
+/// A `thorough` registration re-sends everything, and is only
+/// coalesced with other thorough registrations.
+async fn register_with_hass_debounced(
+    state: &Arc<Pv2MqttState>,
+    thorough: bool,
+) -> anyhow::Result<()> {
+    let last = if thorough {
+        *state.last_thorough_registration.lock().unwrap()
+    } else {
+        *state.last_registration.lock().unwrap()
+    };
+    if let Some(last) = last {
+        if last.elapsed() < REGISTRATION_DEBOUNCE {
+            log::debug!(
+                "skipping registration; we registered {:?} ago",
+                last.elapsed()
+            );
+            return Ok(());
+        }
+    }
+
+    if thorough {
+        // Make apply_updates be more thorough
+        state.first_run.store(true, Ordering::SeqCst);
+    }
+    register_with_hass(state).await
+}
+
 async fn register_with_hass(state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
+    let thorough = state.first_run.load(Ordering::SeqCst);
     let mut reg = HassRegistration::new();
+    let capabilities = state
+        .hub
+        .load()
+        .hub
+        .get_capabilities()
+        .await
+        .context("get_capabilities")?;
 
     register_hub(&state.hub.load().user_data, state, &mut reg)
         .await
@@ -742,18 +1230,31 @@ async fn register_with_hass(state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
     register_shades(state, &mut reg)
         .await
         .context("register_shades")?;
-    register_scenes(state, &mut reg)
-        .await
-        .context("register_scenes")?;
+    if capabilities.scenes {
+        register_scenes(state, &mut reg)
+            .await
+            .context("register_scenes")?;
+    }
+    if capabilities.scene_collections {
+        register_scene_collections(state, &mut reg)
+            .await
+            .context("register_scene_collections")?;
+    }
     reg.apply_updates(state).await.context("apply_updates")?;
+
+    let now = Some(Instant::now());
+    *state.last_registration.lock().unwrap() = now;
+    if thorough {
+        *state.last_thorough_registration.lock().unwrap() = now;
+    }
     Ok(())
 }
 
 async fn advise_hass_of_unresponsive(state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
     log::info!("Marking hub status as unresponsive");
     state.responding.store(false, Ordering::SeqCst);
+    state.metrics.set_hub_responding(false);
     state
-        .client
         .publish(
             format!("{MODEL}/sensor/{}-responding/state", state.serial),
             "UNRESPONSIVE",
@@ -770,7 +1271,6 @@ async fn advise_hass_of_state_label(
     shade_state: &str,
 ) -> anyhow::Result<()> {
     state
-        .client
         .publish(
             &format!(
                 "{MODEL}/shade/{serial}/{shade_id}/state",
@@ -789,8 +1289,9 @@ async fn advise_hass_of_position(
     shade_id: &str,
     position: u8,
 ) -> anyhow::Result<()> {
+    let position = state.round_position(position);
+    state.metrics.set_shade_position(shade_id, position);
     state
-        .client
         .publish(
             &format!(
                 "{MODEL}/shade/{serial}/{shade_id}/position",
@@ -805,27 +1306,148 @@ async fn advise_hass_of_position(
     Ok(())
 }
 
+async fn advise_hass_of_target_position(
+    state: &Arc<Pv2MqttState>,
+    shade_id: &str,
+    position: u8,
+) -> anyhow::Result<()> {
+    let position = state.round_position(position);
+    state
+        .publish(
+            format!(
+                "{MODEL}/shade/{serial}/{shade_id}/target_position",
+                serial = state.serial
+            ),
+            format!("{position}"),
+            QoS::AtMostOnce,
+            false,
+        )
+        .await?;
+    Ok(())
+}
+
+/// How often to publish the estimated position of a moving shade
+const MOTION_ESTIMATE_INTERVAL: Duration = Duration::from_secs(1);
+
+fn cancel_motion_estimate(state: &Arc<Pv2MqttState>, shade_id: &str) {
+    if let Some(task) = state.motion_estimates.lock().unwrap().remove(shade_id) {
+        task.abort();
+    }
+}
+
+/// If enabled, and the postback has enough information, spawn a task
+/// that publishes the estimated position of the shade as it moves
+/// toward its target, assuming that it moves at a constant speed
+fn start_motion_estimate(
+    state: &Arc<Pv2MqttState>,
+    shade_id: &str,
+    item: &HomeAutomationPostBackData,
+) {
+    if !state.estimate_motion {
+        return;
+    }
+    let (Some(start), Some(target), Some(duration_ms)) = (
+        item.current_position.or(item.initial_position),
+        item.target_position,
+        item.remaining_duration_ms.or(item.duration_ms),
+    ) else {
+        return;
+    };
+    if duration_ms <= 0 || start == target {
+        return;
+    }
+    let duration = Duration::from_millis(duration_ms as u64);
+
+    let task_state = Arc::clone(state);
+    let task_shade_id = shade_id.to_string();
+    let task = tokio::spawn(async move {
+        let begin = Instant::now();
+        loop {
+            tokio::time::sleep(MOTION_ESTIMATE_INTERVAL).await;
+            let elapsed = begin.elapsed();
+            if elapsed >= duration {
+                // The postback for the end of the motion will
+                // report where the shade actually stopped
+                break;
+            }
+            let fraction = elapsed.as_secs_f64() / duration.as_secs_f64();
+            let estimate = start as f64 + (target as f64 - start as f64) * fraction;
+            if let Err(err) =
+                advise_hass_of_position(&task_state, &task_shade_id, estimate.round() as u8).await
+            {
+                log::error!("publishing estimated position of {task_shade_id}: {err:#}");
+            }
+        }
+    });
+
+    if let Some(prior) = state
+        .motion_estimates
+        .lock()
+        .unwrap()
+        .insert(shade_id.to_string(), task)
+    {
+        prior.abort();
+    }
+}
+
+/// Fetch the current position of every shade and report
+/// the positions and open/closed state to hass
+async fn poll_shade_positions(state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
+    let shades = state.hub.load().hub.list_shades(None, None).await?;
+    for shade in &shades {
+        let Some(position) = &shade.positions else {
+            continue;
+        };
+
+        let mut rails = vec![(shade.id.to_string(), Some(position.pos1_percent()))];
+        if shade
+            .capabilities
+            .flags()
+            .contains(ShadeCapabilityFlags::SECONDARY_RAIL)
+        {
+            rails.push((
+                format!("{}{SECONDARY_SUFFIX}", shade.id),
+                position.pos2_percent(),
+            ));
+        }
+
+        for (shade_id, pos) in rails {
+            if let Some(pos) = pos {
+                advise_hass_of_position_and_state(state, &shade_id, pos).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Report both the position and the open/closed state of a shade
+async fn advise_hass_of_position_and_state(
+    state: &Arc<Pv2MqttState>,
+    shade_id: &str,
+    position: u8,
+) -> anyhow::Result<()> {
+    advise_hass_of_position(state, shade_id, position).await?;
+    let label = if state.round_position(position) == 0 {
+        "closed"
+    } else {
+        "open"
+    };
+    advise_hass_of_state_label(state, shade_id, label).await
+}
+
 async fn advise_hass_of_updated_position(
     state: &Arc<Pv2MqttState>,
     shade: &ShadeData,
 ) -> anyhow::Result<()> {
     if let Some(pct) = shade.pos1_percent() {
-        advise_hass_of_position(&state, &format!("{}", shade.id), pct).await?;
+        advise_hass_of_position(state, &format!("{}", shade.id), pct).await?;
     }
     if let Some(pct) = shade.pos2_percent() {
-        advise_hass_of_position(&state, &format!("{}{SECONDARY_SUFFIX}", shade.id), pct).await?;
+        advise_hass_of_position(state, &format!("{}{SECONDARY_SUFFIX}", shade.id), pct).await?;
     }
     Ok(())
 }
 
-fn battery_kind_to_state(kind: ShadeBatteryKind) -> &'static str {
-    match kind {
-        ShadeBatteryKind::HardWiredPowerSupply => HARD_WIRED_LABEL,
-        ShadeBatteryKind::BatteryWand => BATTERY_LABEL,
-        ShadeBatteryKind::RechargeableBattery => RECHARGEABLE_LABEL,
-    }
-}
-
 async fn advise_hass_of_battery_kind(
     state: &Arc<Pv2MqttState>,
     shade: &ShadeData,
@@ -833,10 +1455,9 @@ async fn advise_hass_of_battery_kind(
     let state_topic = state.battery_kind_state_topic(shade);
 
     state
-        .client
         .publish(
             state_topic,
-            battery_kind_to_state(shade.battery_kind),
+            shade.battery_kind.label(),
             QoS::AtMostOnce,
             false,
         )
@@ -854,16 +1475,16 @@ async fn advise_hass_of_battery_level(
 
     if let Some(pct) = shade.battery_percent() {
         state
-            .client
+            .metrics
+            .set_battery_percent(&shade.id.to_string(), pct);
+        state
             .publish(state_topic, format!("{pct}"), QoS::AtMostOnce, false)
             .await?;
         state
-            .client
             .publish(availability_topic, "online", QoS::AtMostOnce, false)
             .await?;
     } else {
         state
-            .client
             .publish(availability_topic, "offline", QoS::AtMostOnce, false)
             .await?;
     }
@@ -872,68 +1493,10 @@ async fn advise_hass_of_battery_level(
 }
 
 impl ServeMqttCommand {
-    async fn setup_http_server(&self, tx: Sender<ServerEvent>) -> anyhow::Result<u16> {
-        // Figure out our local ip when talking to the hub
-        use axum::extract::State;
-        use axum::http::StatusCode;
-        use axum::response::{IntoResponse, Response};
-        use axum::routing::post;
-        use axum::Router;
-        use base64::engine::Engine;
-
-        fn generic<T: ToString + std::fmt::Display>(err: T) -> Response {
-            log::error!("err: {err:#}");
-            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
-        }
-
-        /// The hook data is sent with `Content-Type: application/x-www-form-urlencoded`
-        /// but the data is most often actually base64 encoded json, so we just have
-        /// to ignore the content type and extract from the data ourselves.
-        async fn pv_postback(
-            State(tx): State<Sender<ServerEvent>>,
-            Path(serial): Path<String>,
-            body: String,
-        ) -> Result<Response, Response> {
-            #[derive(Deserialize, Debug)]
-            #[serde(rename_all = "camelCase")]
-            #[serde(deny_unknown_fields)]
-            pub struct ConfigUpdate {
-                pub config_num: i64,
-            }
-
-            if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(&body) {
-                let data: Vec<HomeAutomationPostBackData> =
-                    serde_json::from_slice(&decoded).map_err(generic)?;
-                log::debug!("postback: {data:?}");
-                tx.send(ServerEvent::HomeAutomationData { serial, data })
-                    .await
-                    .map_err(generic)?;
-            } else if let Ok(config) = serde_urlencoded::from_str::<ConfigUpdate>(&body) {
-                log::warn!(
-                    "** A shade failed post-move verification. New configuration {config:?}"
-                );
-            } else {
-                log::error!("** Not sure what to do with {body}");
-            }
-            Ok((StatusCode::OK, "").into_response())
-        }
-
-        let app = Router::new()
-            .route("/pv-postback/:serial", post(pv_postback))
-            .with_state(tx);
-
-        let listener = tokio::net::TcpListener::bind(("0.0.0.0", 0)).await?;
-        let addr = listener.local_addr()?;
-        log::info!("http server addr is {addr:?}");
-        tokio::spawn(async {
-            if let Err(err) = axum::serve(listener, app).await {
-                log::error!("http server stopped: {err:#}");
-            }
-        });
-        Ok(addr.port())
-    }
-
     pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        // This must happen before we connect to the hub
+        crate::hub::set_request_concurrency(self.hub_concurrency.into());
+
         let mqtt_host = match &self.host {
             Some(h) => h.to_string(),
             None => std::env::var("PV_MQTT_HOST").context(
@@ -955,6 +1518,15 @@ impl ServeMqttCommand {
             Some(u) => Some(u),
             None => opt_env_var("PV_MQTT_PASSWORD")?,
         };
+        let mqtt_keepalive = Duration::from_secs(match self.mqtt_keepalive {
+            Some(k) => k,
+            None => opt_env_var("PV_MQTT_KEEPALIVE")?.unwrap_or(10),
+        });
+
+        let schedule = match &self.schedule_file {
+            Some(path) => ScheduleFile::load(path)?,
+            None => ScheduleFile::default(),
+        };
 
         let (tx, rx) = tokio::sync::mpsc::channel(32);
 
@@ -969,7 +1541,17 @@ impl ServeMqttCommand {
         })?;
         let serial = &user_data.serial_number.to_string();
 
-        let http_port = self.setup_http_server(tx.clone()).await?;
+        let metrics = Arc::new(Metrics::new());
+        let extra_routes = if self.metrics {
+            metrics_router(Arc::clone(&metrics))
+        } else {
+            axum::Router::new()
+        };
+        let http_port =
+            start_postback_server_with_routes(tx.clone(), extra_routes, self.http_port).await?;
+        if self.metrics {
+            log::info!("Serving metrics at http://0.0.0.0:{http_port}/metrics");
+        }
 
         let client = Client::with_auto_id()?;
 
@@ -982,22 +1564,66 @@ impl ServeMqttCommand {
             serial: serial.clone(),
             http_port,
             discovery_prefix: self.discovery_prefix.clone(),
+            position_precision: self.position_precision,
             first_run: AtomicBool::new(true),
             responding: AtomicBool::new(true),
+            availability_topics: Mutex::new(BTreeSet::new()),
+            scene_activation_debounce: Duration::from_millis(self.scene_activation_debounce_ms),
+            scene_activations: Mutex::new(HashMap::new()),
+            last_config_payloads: Mutex::new(HashMap::new()),
+            last_registration: Mutex::new(None),
+            last_thorough_registration: Mutex::new(None),
+            mqtt_keepalive,
+            entity_name_template: self.entity_name_template.clone(),
+            config_num: AtomicI64::new(0),
+            bridge_availability_topic: self
+                .bridge_availability_topic
+                .clone()
+                .unwrap_or_else(|| format!("{MODEL}/bridge/{serial}/availability")),
+            retain_config: self.retain_config,
+            state_update_on_command: self.state_update_on_command,
+            metrics,
+            estimate_motion: self.estimate_motion,
+            optimistic_shade_types: self.optimistic_shade_types.clone(),
+            motion_estimates: Mutex::new(HashMap::new()),
+            dry_run: self.dry_run,
         });
 
         self.update_homeautomation_hook(&state).await?;
 
         client.set_username_and_password(mqtt_username.as_deref(), mqtt_password.as_deref())?;
-        client
-            .connect(
-                &mqtt_host,
-                mqtt_port.into(),
-                Duration::from_secs(10),
-                self.bind_address.as_deref(),
-            )
-            .await
-            .with_context(|| format!("connecting to mqtt broker {mqtt_host}:{mqtt_port}"))?;
+        // Once connected, the client will automatically reconnect
+        // after this delay if the connection is lost
+        client.set_reconnect_delay(self.reconnect_delay, self.reconnect_delay, false)?;
+        if !self.dry_run {
+            client.set_last_will(
+                &state.bridge_availability_topic,
+                "offline",
+                QoS::AtMostOnce,
+                true,
+            )?;
+        }
+        let mut attempt = 1;
+        loop {
+            match client
+                .connect(
+                    &mqtt_host,
+                    mqtt_port.into(),
+                    state.mqtt_keepalive,
+                    self.bind_address.as_deref(),
+                )
+                .await
+                .with_context(|| format!("connecting to mqtt broker {mqtt_host}:{mqtt_port}"))
+            {
+                Ok(_) => break,
+                Err(err) if !self.reconnect_attempts_exhausted(attempt) => {
+                    log::warn!("{err:#}. Will retry in {:?}", self.reconnect_delay);
+                    tokio::time::sleep(self.reconnect_delay).await;
+                    attempt += 1;
+                }
+                Err(err) => return Err(err),
+            }
+        }
         let subscriber = client.subscriber().expect("to own the subscriber");
 
         async fn rebuild_router(
@@ -1014,27 +1640,57 @@ impl ServeMqttCommand {
                 )
                 .await?;
 
-            router
-                .route(
-                    format!("{MODEL}/scene/:serial/:scene_id/set"),
-                    mqtt_scene_activate,
-                )
-                .await?;
+            // Commands from Home Assistant would change the state of the
+            // hub, so don't listen for them in dry-run mode
+            if state.dry_run {
+                log::info!("dry-run: ignoring commands from Home Assistant");
+            } else {
+                router
+                    .route(
+                        format!("{MODEL}/scene/:serial/:scene_id/set"),
+                        mqtt_scene_activate,
+                    )
+                    .await?;
+
+                router
+                    .route(
+                        format!("{MODEL}/scene_collection/:serial/:collection_id/set"),
+                        mqtt_scene_collection_activate,
+                    )
+                    .await?;
+
+                router
+                    .route(
+                        format!("{MODEL}/shade/:serial/:shade_id/set_position"),
+                        mqtt_shade_set_position,
+                    )
+                    .await?;
+                router
+                    .route(
+                        format!("{MODEL}/shade/:serial/:shade_id/set_tilt"),
+                        mqtt_shade_set_tilt,
+                    )
+                    .await?;
+                router
+                    .route(
+                        format!("{MODEL}/shade/:serial/:shade_id/command"),
+                        mqtt_shade_command,
+                    )
+                    .await?;
+            }
 
-            router
-                .route(
-                    format!("{MODEL}/shade/:serial/:shade_id/set_position"),
-                    mqtt_shade_set_position,
-                )
-                .await?;
-            router
-                .route(
-                    format!("{MODEL}/shade/:serial/:shade_id/command"),
-                    mqtt_shade_command,
+            // The broker may have published our last will while we
+            // were disconnected, so always say that we are back
+            state
+                .publish(
+                    &state.bridge_availability_topic,
+                    "online",
+                    QoS::AtMostOnce,
+                    true,
                 )
                 .await?;
 
-            register_with_hass(&state).await?;
+            register_with_hass(state).await?;
             Ok(Arc::new(router))
         }
 
@@ -1054,6 +1710,33 @@ impl ServeMqttCommand {
             });
         }
 
+        if let Some(poll_interval) = self.poll_interval {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(poll_interval).await;
+                    if let Err(err) = tx.send(ServerEvent::PollShadePositions).await {
+                        log::error!("{err:#?}");
+                        break;
+                    }
+                }
+            });
+        }
+
+        // Keep the scheduler alive for as long as we are serving
+        let _scheduler = self.start_scheduler(schedule, &state, tx.clone()).await?;
+
+        {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                crate::shutdown_signal().await;
+                log::info!("Shutting down");
+                if let Err(err) = tx.send(ServerEvent::Shutdown).await {
+                    log::error!("{err:#?}");
+                }
+            });
+        }
+
         if !args.hub_ip_was_specified_by_user() {
             let tx = tx.clone();
             let serial = args.hub_serial()?;
@@ -1068,7 +1751,10 @@ impl ServeMqttCommand {
                             }
                         }
 
-                        if let Err(err) = tx.send(ServerEvent::HubDiscovered(resolved_hub)).await {
+                        if let Err(err) = tx
+                            .send(ServerEvent::HubDiscovered(Box::new(resolved_hub)))
+                            .await
+                        {
                             log::error!("discovery: send to main thread: {err:#}");
                             break;
                         }
@@ -1079,9 +1765,20 @@ impl ServeMqttCommand {
         }
 
         {
+            let tx = tx.clone();
             let state = state.clone();
             let discovery_prefix = self.discovery_prefix.to_string();
+            let reconnect_delay = self.reconnect_delay;
+            let max_reconnect_attempts = self.max_reconnect_attempts;
             tokio::spawn(async move {
+                // The number of consecutive failed attempts to reconnect
+                let mut failed_attempts = 0;
+                let exhausted = |failed: usize| {
+                    max_reconnect_attempts
+                        .map(|max| failed >= max)
+                        .unwrap_or(false)
+                };
+
                 while let Ok(event) = subscriber.recv().await {
                     match event {
                         Event::Message(msg) => {
@@ -1097,29 +1794,131 @@ impl ServeMqttCommand {
                             }
                         }
                         Event::Disconnected(reason) => {
+                            if need_rebuild {
+                                // We were already disconnected, so this
+                                // represents a failed reconnection attempt
+                                failed_attempts += 1;
+                            }
                             log::warn!("MQTT disconnected: {reason}");
                             need_rebuild = true;
+                            if exhausted(failed_attempts) {
+                                log::error!(
+                                    "Giving up after {failed_attempts} attempts to reconnect"
+                                );
+                                break;
+                            }
                         }
                         Event::Connected(status) => {
                             log::info!("MQTT (re)connected {status}");
                             if need_rebuild {
-                                match rebuild_router(&client, &state, &discovery_prefix).await {
-                                    Err(err) => {
-                                        log::error!("Rebuilding router: {err:#}");
-                                        break;
-                                    }
-                                    Ok(r) => {
-                                        router = r;
+                                // Re-subscribe and re-register with hass,
+                                // as the broker may have lost our state
+                                loop {
+                                    match rebuild_router(&client, &state, &discovery_prefix).await {
+                                        Ok(r) => {
+                                            router = r;
+                                            need_rebuild = false;
+                                            failed_attempts = 0;
+                                            break;
+                                        }
+                                        Err(err) => {
+                                            failed_attempts += 1;
+                                            log::error!("Rebuilding router: {err:#}");
+                                            if exhausted(failed_attempts) {
+                                                break;
+                                            }
+                                            tokio::time::sleep(reconnect_delay).await;
+                                        }
                                     }
                                 }
+                                if need_rebuild {
+                                    log::error!(
+                                        "Giving up after {failed_attempts} attempts \
+                                         to rebuild the router"
+                                    );
+                                    break;
+                                }
                             }
                         }
                     }
                 }
+
+                // We can no longer receive mqtt messages, so there is
+                // no point in continuing to run
+                log::error!("No longer connected to the mqtt broker; shutting down");
+                if let Err(err) = tx.send(ServerEvent::Shutdown).await {
+                    log::error!("{err:#?}");
+                }
             });
         }
 
-        self.serve(rx, state).await;
+        self.serve(tx, rx, state).await;
+        Ok(())
+    }
+
+    async fn start_scheduler(
+        &self,
+        schedule: ScheduleFile,
+        state: &Arc<Pv2MqttState>,
+        tx: Sender<ServerEvent>,
+    ) -> anyhow::Result<Option<JobScheduler>> {
+        if schedule.schedule.is_empty() {
+            return Ok(None);
+        }
+
+        let scheduler = JobScheduler::new().await?;
+        let tz = crate::resolve_timezone();
+
+        for entry in schedule.schedule {
+            if let Err(err) = state.hub.load().hub.shade_by_id_or_name(&entry.shade).await {
+                log::warn!("schedule {entry:?}: {err:#}");
+            }
+
+            let cron = entry.cron_with_seconds();
+            let tx = tx.clone();
+            let job = Job::new_async_tz(cron.as_str(), tz, move |_uuid, _lock| {
+                let tx = tx.clone();
+                let entry = entry.clone();
+                Box::pin(async move {
+                    // Route through the main loop, so that the move is
+                    // serialized with respect to the other hub requests
+                    if let Err(err) = tx.send(ServerEvent::ScheduledMove(entry)).await {
+                        log::error!("scheduler: send to main thread: {err:#}");
+                    }
+                })
+            })
+            .with_context(|| format!("invalid cron expression '{cron}'"))?;
+            scheduler.add(job).await?;
+        }
+
+        scheduler.start().await?;
+        Ok(Some(scheduler))
+    }
+
+    async fn handle_scheduled_move(
+        &self,
+        state: &Arc<Pv2MqttState>,
+        entry: ScheduleEntry,
+    ) -> anyhow::Result<()> {
+        // Resolve the shade each time, rather than at startup, so that
+        // we pick up the current hub address and any renamed shades
+        let hub = state.hub.load();
+        let shade = hub.hub.shade_by_id_or_name(&entry.shade).await?;
+
+        let mut position = shade
+            .positions
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("shade {} has no existing position", shade.id))?;
+
+        position.set_rail_percent(shade.is_primary(), entry.percent);
+
+        log::info!(
+            "Scheduled move of {} {} to {}%",
+            shade.id,
+            shade.name(),
+            entry.percent
+        );
+        hub.hub.change_shade_position(shade.id, position).await?;
         Ok(())
     }
 
@@ -1130,6 +1929,7 @@ impl ServeMqttCommand {
         router: &MqttRouter<Arc<Pv2MqttState>>,
     ) -> anyhow::Result<()> {
         log::debug!("msg: {msg:?}");
+        state.metrics.mqtt_message_dispatched();
         Ok(router.dispatch(msg, Arc::clone(state)).await?)
     }
 
@@ -1147,12 +1947,28 @@ impl ServeMqttCommand {
             }
         };
 
+        if !matches!(
+            item.record_type,
+            HomeAutomationRecordType::StartsOpening | HomeAutomationRecordType::StartsClosing
+        ) {
+            // We have more recent information than the estimate
+            cancel_motion_estimate(state, &shade_id);
+        }
+
+        if let Some(target) = item.target_position {
+            advise_hass_of_target_position(state, &shade_id, target).await?;
+        }
+
         match item.record_type {
             HomeAutomationRecordType::Stops => {
                 if let Some(pct) = item.stopped_position {
                     advise_hass_of_position(state, &shade_id, pct).await?;
 
-                    let shade_state = if pct == 0 { "closed" } else { "open" };
+                    let shade_state = if state.round_position(pct) == 0 {
+                        "closed"
+                    } else {
+                        "open"
+                    };
                     advise_hass_of_state_label(state, &shade_id, shade_state).await?;
                 }
             }
@@ -1160,6 +1976,7 @@ impl ServeMqttCommand {
                 if let Some(pct) = item.current_position {
                     advise_hass_of_position(state, &shade_id, pct).await?;
                 }
+                start_motion_estimate(state, &shade_id, &item);
             }
             HomeAutomationRecordType::StartsClosing => {
                 advise_hass_of_state_label(state, &shade_id, "closing").await?;
@@ -1173,26 +1990,68 @@ impl ServeMqttCommand {
             HomeAutomationRecordType::HasClosed | HomeAutomationRecordType::HasFullyClosed => {
                 advise_hass_of_state_label(state, &shade_id, "closed").await?;
             }
-            HomeAutomationRecordType::TargetLevelChanged => {}
-            HomeAutomationRecordType::LevelChanged => {}
+            HomeAutomationRecordType::TargetLevelChanged => {
+                // The target was published above; the shade may
+                // now take a different amount of time to get there
+                start_motion_estimate(state, &shade_id, &item);
+            }
+            HomeAutomationRecordType::LevelChanged => {
+                if let Some(pct) = item.current_position {
+                    advise_hass_of_position(state, &shade_id, pct).await?;
+                }
+            }
         }
         Ok(())
     }
 
     async fn update_homeautomation_hook(&self, state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
+        if state.dry_run {
+            log::info!("dry-run: not enabling the home automation hook");
+            return Ok(());
+        }
         let hub = state.hub.load();
 
-        let addr = hub.hub.suggest_bind_address().await?;
+        let addr = match &self.preferred_network_interface {
+            Some(name) => interface_address(name)?,
+            None => hub.hub.suggest_bind_address().await?,
+        };
         hub.hub
-            .enable_home_automation_hook(&format!(
-                "{addr}:{http_port}/pv-postback/{serial}",
-                http_port = state.http_port,
-                serial = state.serial
-            ))
+            .enable_home_automation_hook(&postback_url(addr, state.http_port, &state.serial))
             .await?;
         Ok(())
     }
 
+    /// Mark all of our entities as unavailable, and stop the hub
+    /// from trying to post events to us after we have gone
+    async fn shutdown(&self, state: &Arc<Pv2MqttState>) -> anyhow::Result<()> {
+        let topics = state.availability_topics.lock().unwrap().clone();
+        for topic in topics {
+            state
+                .publish(&topic, "offline", QoS::AtMostOnce, false)
+                .await?;
+        }
+        state
+            .publish(
+                &state.bridge_availability_topic,
+                "offline",
+                QoS::AtMostOnce,
+                true,
+            )
+            .await?;
+
+        if state.dry_run {
+            return Ok(());
+        }
+        state
+            .hub
+            .load()
+            .hub
+            .disable_home_automation_hook()
+            .await
+            .context("disable_home_automation_hook")?;
+        Ok(())
+    }
+
     async fn handle_discovery(
         &self,
         state: &Arc<Pv2MqttState>,
@@ -1212,9 +2071,10 @@ impl ServeMqttCommand {
                     return Ok(());
                 }
 
-                log::info!("Hub ip, name or connectivity status changed");
+                log::info!("Hub ip, name or connectivity status changed: {user_data}");
 
                 state.responding.store(true, Ordering::SeqCst);
+                state.metrics.set_hub_responding(true);
                 state.hub.store(Arc::new(FullyResolvedHub {
                     hub: hub.hub.clone(),
                     user_data,
@@ -1222,7 +2082,7 @@ impl ServeMqttCommand {
                 self.update_homeautomation_hook(state)
                     .await
                     .context("update_homeautomation_hook")?;
-                register_with_hass(&state)
+                register_with_hass(state)
                     .await
                     .context("register_with_hass")?;
                 Ok(())
@@ -1238,19 +2098,42 @@ impl ServeMqttCommand {
         }
     }
 
-    async fn serve(&self, mut rx: Receiver<ServerEvent>, state: Arc<Pv2MqttState>) {
+    fn reconnect_attempts_exhausted(&self, attempts: usize) -> bool {
+        self.max_reconnect_attempts
+            .map(|max| attempts >= max)
+            .unwrap_or(false)
+    }
+
+    async fn serve(
+        &self,
+        tx: Sender<ServerEvent>,
+        mut rx: Receiver<ServerEvent>,
+        state: Arc<Pv2MqttState>,
+    ) {
         log::info!(
             "Version {}. Waiting for mqtt and pv messages",
             pview_version()
         );
         while let Some(msg) = rx.recv().await {
             match msg {
+                ServerEvent::Shutdown => {
+                    if let Err(err) = self.shutdown(&state).await {
+                        log::error!("During shutdown: {err:#}");
+                    }
+                    break;
+                }
+
                 ServerEvent::MqttMessage { msg, router } => {
                     if let Err(err) = self.handle_mqtt_message(msg, &state, &router).await {
                         log::error!("handling mqtt message: {err:#}");
                     }
                 }
-                ServerEvent::HomeAutomationData { serial, mut data } => {
+                ServerEvent::HomeAutomationData(PostbackEvent {
+                    serial,
+                    mut data,
+                    config_num,
+                }) => {
+                    state.metrics.postback_received();
                     if serial != state.serial {
                         log::warn!(
                             "ignoring postback which is intended for \
@@ -1260,9 +2143,13 @@ impl ServeMqttCommand {
                         continue;
                     }
 
+                    if let Some(config_num) = config_num {
+                        schedule_config_change_registration(&state, &tx, config_num);
+                    }
+
                     // Re-order the events so that the closed/open events happen
                     // after closing/opening
-                    data.sort_by(|a, b| a.record_type.cmp(&b.record_type));
+                    data.sort_by_key(|a| a.record_type);
 
                     for item in data {
                         if let Err(err) = self.handle_pv_event(&state, item).await {
@@ -1272,28 +2159,48 @@ impl ServeMqttCommand {
                 }
 
                 ServerEvent::HubDiscovered(resolved_hub) => {
-                    if let Err(err) = self.handle_discovery(&state, resolved_hub).await {
+                    if let Err(err) = self.handle_discovery(&state, *resolved_hub).await {
                         log::error!("During handle_discovery: {err:#?}");
                     }
                 }
 
+                ServerEvent::ScheduledMove(entry) if state.dry_run => {
+                    log::info!("dry-run: skipping scheduled move {entry:?}");
+                }
+                ServerEvent::ScheduledMove(entry) => {
+                    if let Err(err) = self.handle_scheduled_move(&state, entry).await {
+                        log::error!("During scheduled move: {err:#}");
+                    }
+                }
+
+                ServerEvent::ConfigChanged(config_num) => {
+                    if state.config_num.load(Ordering::SeqCst) != config_num {
+                        // A later change superseded this one, and will
+                        // take care of registering
+                        continue;
+                    }
+                    if let Err(err) = register_with_hass_debounced(&state, true).await {
+                        log::error!("register_with_hass after config change: {err:#}");
+                    }
+                }
+
+                ServerEvent::PollShadePositions => {
+                    if let Err(err) = poll_shade_positions(&state).await {
+                        log::error!("While polling shade positions: {err:#}");
+                    }
+                }
+
                 ServerEvent::PeriodicStateUpdate => {
-                    if let Err(err) = register_with_hass(&state).await {
+                    if let Err(err) = register_with_hass_debounced(&state, false).await {
                         log::error!("During register_with_hass: {err:#?}");
 
                         let mut unresponsive = false;
 
-                        // Look for a request error; it isn't the root cause but rather
-                        // the penultimate cause, so we have to walk the chain to find it.
+                        // The HubError isn't the root cause but rather is wrapped
+                        // in context, so we have to walk the chain to find it.
                         for cause in err.chain() {
-                            if let Some(http_err) = cause.downcast_ref::<reqwest::Error>() {
-                                if http_err.is_connect() {
-                                    unresponsive = true;
-                                    break;
-                                }
-                            }
-                            if cause.downcast_ref::<LockedError>().is_some() {
-                                unresponsive = true;
+                            if let Some(hub_err) = cause.downcast_ref::<HubError>() {
+                                unresponsive = hub_err.is_unresponsive();
                                 break;
                             }
                         }
@@ -1331,7 +2238,46 @@ async fn mqtt_scene_activate(
         return Ok(());
     }
 
-    state.hub.load().hub.activate_scene(scene_id).await?;
+    if !state.should_activate_scene(scene_id) {
+        log::info!("ignoring repeated activation of scene {scene_id}");
+        return Ok(());
+    }
+
+    let hub = state.hub.load();
+    let scene = hub.hub.get_scene_by_id(scene_id).await?;
+    log::info!("Activating scene {scene_id} {}", scene.name);
+    hub.hub.activate_scene(scene_id).await?;
+    state.record_scene_activation(scene_id);
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct SerialAndSceneCollection {
+    serial: String,
+    #[serde(deserialize_with = "parse_deser")]
+    collection_id: i32,
+}
+
+async fn mqtt_scene_collection_activate(
+    Params(SerialAndSceneCollection {
+        serial,
+        collection_id,
+    }): Params<SerialAndSceneCollection>,
+    Topic(topic): Topic,
+    State(state): State<Arc<Pv2MqttState>>,
+) -> anyhow::Result<()> {
+    if serial != state.serial {
+        log::warn!(
+            "ignoring {topic} which is intended for \
+                    serial={serial}, while we are serial {actual_serial}",
+            actual_serial = state.serial
+        );
+        return Ok(());
+    }
+
+    let hub = state.hub.load();
+    log::info!("Activating scene collection {collection_id}");
+    hub.hub.activate_scene_collection(collection_id).await?;
     Ok(())
 }
 
@@ -1393,13 +2339,7 @@ async fn mqtt_shade_set_position(
         .clone()
         .ok_or_else(|| anyhow::anyhow!("shade {shade_id} has no existing position"))?;
 
-    let absolute = ShadePosition::percent_to_pos(position);
-
-    if is_secondary {
-        shade_pos.position_2.replace(absolute);
-    } else {
-        shade_pos.position_1 = absolute;
-    }
+    shade_pos.set_rail_percent(!is_secondary, position);
 
     log::info!(
         "Set {shade_id} {} position to {position} ({shade_pos:?})",
@@ -1409,6 +2349,61 @@ async fn mqtt_shade_set_position(
         .change_shade_position(shade_id, shade_pos.clone())
         .await?;
 
+    if state.state_update_on_command {
+        let addr = if is_secondary {
+            format!("{shade_id}{SECONDARY_SUFFIX}")
+        } else {
+            format!("{shade_id}")
+        };
+        advise_hass_of_position_and_state(&state, &addr, position).await?;
+    }
+
+    Ok(())
+}
+
+async fn mqtt_shade_set_tilt(
+    params: Params<SerialAndShade>,
+    Topic(topic): Topic,
+    State(state): State<Arc<Pv2MqttState>>,
+    Payload(tilt): Payload<u8>,
+) -> anyhow::Result<()> {
+    let Params(SerialAndShade {
+        serial,
+        shade_id: ShadeIdAddr {
+            shade_id,
+            is_secondary: _,
+        },
+    }) = params;
+
+    if serial != state.serial {
+        log::warn!(
+            "ignoring {topic} which is intended for \
+                    serial={serial}, while we are serial {actual_serial}",
+            actual_serial = state.serial
+        );
+        return Ok(());
+    }
+
+    let hub = state.hub.load();
+    let shade = hub.hub.shade_by_id(shade_id).await?;
+    let position = shade.tilt_position(tilt);
+
+    log::info!(
+        "Set {shade_id} {} tilt to {tilt} ({position:?})",
+        shade.name()
+    );
+    let shade = hub.hub.change_shade_position(shade_id, position).await?;
+    if let Some(tilt) = shade.tilt_percent() {
+        state
+            .publish(
+                format!("{MODEL}/shade/{serial}/{shade_id}/tilt"),
+                format!("{tilt}"),
+                QoS::AtMostOnce,
+                false,
+            )
+            .await?;
+    }
+
     Ok(())
 }
 
@@ -1442,14 +2437,22 @@ async fn mqtt_shade_command(
     match command.as_ref() {
         "OPEN" => {
             let shade = hub.hub.move_shade(shade_id, ShadeUpdateMotion::Up).await?;
-            advise_hass_of_updated_position(&state, &shade).await?;
+            if state.state_update_on_command {
+                advise_hass_of_position_and_state(&state, &format!("{shade_id}"), 100).await?;
+            } else {
+                advise_hass_of_updated_position(&state, &shade).await?;
+            }
         }
         "CLOSE" => {
             let shade = hub
                 .hub
                 .move_shade(shade_id, ShadeUpdateMotion::Down)
                 .await?;
-            advise_hass_of_updated_position(&state, &shade).await?;
+            if state.state_update_on_command {
+                advise_hass_of_position_and_state(&state, &format!("{shade_id}"), 0).await?;
+            } else {
+                advise_hass_of_updated_position(&state, &shade).await?;
+            }
         }
         "STOP" => {
             let shade = hub
@@ -1470,12 +2473,26 @@ async fn mqtt_shade_command(
             advise_hass_of_updated_position(&state, &shade).await?;
         }
         "HEART" => {
-            let shade = hub
-                .hub
-                .move_shade(shade_id, ShadeUpdateMotion::Heart)
-                .await?;
+            let shade = hub.hub.move_shade_to_favorite(shade_id).await?;
             advise_hass_of_updated_position(&state, &shade).await?;
         }
+        "GROUP_OPEN" | "GROUP_CLOSE" | "GROUP_STOP" => {
+            let motion = match command.as_ref() {
+                "GROUP_OPEN" => ShadeUpdateMotion::Up,
+                "GROUP_CLOSE" => ShadeUpdateMotion::Down,
+                _ => ShadeUpdateMotion::Stop,
+            };
+            // Apply the motion to every shade in the same group
+            // as the shade that received the command
+            for member in hub.hub.list_shades(Some(shade.group_id), None).await? {
+                match hub.hub.move_shade(member.id, motion).await {
+                    Ok(member) => advise_hass_of_updated_position(&state, &member).await?,
+                    Err(err) => {
+                        log::error!("{command} {} {}: {err:#}", member.id, member.name());
+                    }
+                }
+            }
+        }
         "UPDATE_BATTERY" => {
             let shade = hub.hub.shade_update_battery_level(shade_id).await?;
             advise_hass_of_battery_level(&state, &shade).await?;
@@ -1484,21 +2501,21 @@ async fn mqtt_shade_command(
             let shade = hub.hub.shade_refresh_position(shade_id).await?;
             advise_hass_of_updated_position(&state, &shade).await?;
         }
-        BATTERY_LABEL => {
+        ShadeBatteryKind::BATTERY_LABEL => {
             let shade = hub
                 .hub
                 .change_battery_kind(shade_id, ShadeBatteryKind::BatteryWand)
                 .await?;
             advise_hass_of_battery_kind(&state, &shade).await?;
         }
-        RECHARGEABLE_LABEL => {
+        ShadeBatteryKind::RECHARGEABLE_LABEL => {
             let shade = hub
                 .hub
                 .change_battery_kind(shade_id, ShadeBatteryKind::RechargeableBattery)
                 .await?;
             advise_hass_of_battery_kind(&state, &shade).await?;
         }
-        HARD_WIRED_LABEL => {
+        ShadeBatteryKind::HARD_WIRED_LABEL => {
             let shade = hub
                 .hub
                 .change_battery_kind(shade_id, ShadeBatteryKind::HardWiredPowerSupply)
@@ -1518,9 +2535,7 @@ async fn mqtt_homeassitant_status(
     State(state): State<Arc<Pv2MqttState>>,
 ) -> anyhow::Result<()> {
     log::info!("Home Assistant status changed: {status}",);
-    // Make apply_updates be more thorough
-    state.first_run.store(true, Ordering::SeqCst);
-    register_with_hass(&state).await
+    register_with_hass_debounced(&state, true).await
 }
 
 struct FullyResolvedHub {
@@ -1534,11 +2549,89 @@ struct Pv2MqttState {
     serial: String,
     http_port: u16,
     discovery_prefix: String,
+    position_precision: u8,
     first_run: AtomicBool,
     responding: AtomicBool,
+    /// The availability topics that we have published, so that
+    /// we can mark them all offline when we shut down
+    availability_topics: Mutex<BTreeSet<String>>,
+    scene_activation_debounce: Duration,
+    /// When each scene was last activated via mqtt
+    scene_activations: Mutex<HashMap<i32, Instant>>,
+    /// The config payloads we most recently sent, keyed by topic
+    last_config_payloads: Mutex<HashMap<String, String>>,
+    last_registration: Mutex<Option<Instant>>,
+    last_thorough_registration: Mutex<Option<Instant>>,
+    /// The keepalive interval used when connecting to the broker
+    mqtt_keepalive: Duration,
+    entity_name_template: Option<EntityNameTemplate>,
+    /// Where we advertise the online/offline status of the bridge
+    bridge_availability_topic: String,
+    retain_config: bool,
+    /// Publish the target position as soon as a shade is commanded
+    state_update_on_command: bool,
+    metrics: Arc<Metrics>,
+    /// The most recent configuration number reported by the hub
+    config_num: AtomicI64,
+    estimate_motion: bool,
+    /// The shade types for which hass should assume commanded positions
+    optimistic_shade_types: Vec<i32>,
+    /// The tasks publishing estimated positions for moving shades,
+    /// keyed by shade id. Finished tasks are left in place until
+    /// they are replaced or cancelled by the next event for the shade.
+    motion_estimates: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    /// Log rather than publish messages
+    dry_run: bool,
 }
 
 impl Pv2MqttState {
+    /// Publish a message to the broker, or, in dry run mode,
+    /// log the message that would have been published
+    async fn publish<T: AsRef<str>, P: AsRef<[u8]>>(
+        &self,
+        topic: T,
+        payload: P,
+        qos: QoS,
+        retain: bool,
+    ) -> anyhow::Result<()> {
+        if self.dry_run {
+            log::info!(
+                "dry-run: would publish {} (retain={retain}): {}",
+                topic.as_ref(),
+                String::from_utf8_lossy(payload.as_ref())
+            );
+            return Ok(());
+        }
+        self.client.publish(topic, payload, qos, retain).await?;
+        Ok(())
+    }
+
+    /// Returns false if the scene was activated too recently to be
+    /// activated again
+    fn should_activate_scene(&self, scene_id: i32) -> bool {
+        match self.scene_activations.lock().unwrap().get(&scene_id) {
+            Some(last) => last.elapsed() >= self.scene_activation_debounce,
+            None => true,
+        }
+    }
+
+    /// Record that the scene was successfully activated, for
+    /// use by should_activate_scene
+    fn record_scene_activation(&self, scene_id: i32) {
+        self.scene_activations
+            .lock()
+            .unwrap()
+            .insert(scene_id, Instant::now());
+    }
+
+    /// Round a position percentage to the nearest multiple of
+    /// the configured position precision
+    pub fn round_position(&self, pct: u8) -> u8 {
+        let precision = self.position_precision.max(1) as u16;
+        let rounded = (pct as u16 + precision / 2) / precision * precision;
+        rounded.min(100) as u8
+    }
+
     pub fn battery_availability_topic(&self, shade: &ShadeData) -> String {
         format!(
             "{MODEL}/sensor/{}/{}/battery/availability",
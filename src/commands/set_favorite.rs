@@ -0,0 +1,74 @@
+use crate::api_types::{PositionKind, ShadePosition, ShadeUpdateMotion};
+
+/// Program the favorite position of a shade; this is the position
+/// that the shade moves to when the heart button is pressed
+#[derive(clap::Parser, Debug)]
+pub struct SetFavoriteCommand {
+    /// The name or id of the shade
+    name: String,
+
+    /// The favorite position of the shade
+    #[arg(long)]
+    percent: u8,
+
+    /// The favorite position of the secondary rail,
+    /// for top-down/bottom-up shades
+    #[arg(long)]
+    secondary_percent: Option<u8>,
+
+    /// After setting the favorite, move the shade to it,
+    /// so that you can verify that it is correct
+    #[arg(long)]
+    test: bool,
+}
+
+impl SetFavoriteCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let shade = hub.shade_by_id_or_name(&self.name).await?;
+
+        let pos_kind_1 = shade
+            .positions
+            .as_ref()
+            .map(|p| p.pos_kind_1)
+            .unwrap_or(PositionKind::PrimaryRail);
+        let pos_kind_2 = self.secondary_percent.map(|_| {
+            shade
+                .positions
+                .as_ref()
+                .and_then(|p| p.pos_kind_2)
+                .unwrap_or(PositionKind::SecondaryRail)
+        });
+        let favorite = ShadePosition::from_percent_pair(
+            pos_kind_1,
+            self.percent,
+            pos_kind_2,
+            self.secondary_percent,
+        );
+
+        let updated = hub.set_shade_favorite(shade.id, favorite.clone()).await?;
+        // Not all firmware echoes back the favorite, so fall
+        // back to reporting what we asked it to store
+        let stored = updated.favorite.as_ref().unwrap_or(&favorite);
+        println!(
+            "Favorite position of {} is now {}",
+            shade.name(),
+            stored.describe()
+        );
+
+        if self.test {
+            let moved = hub.move_shade(shade.id, ShadeUpdateMotion::Heart).await?;
+            println!(
+                "Moved {} to {}",
+                moved.name(),
+                moved
+                    .positions
+                    .as_ref()
+                    .map(|p| p.describe())
+                    .unwrap_or_else(|| "unknown position".to_string())
+            );
+        }
+
+        Ok(())
+    }
+}
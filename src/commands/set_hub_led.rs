@@ -0,0 +1,75 @@
+use crate::api_types::Color;
+
+/// Change the color and brightness of the LED on the hub.
+/// Channels that are not specified keep their current value.
+#[derive(clap::Parser, Debug)]
+pub struct SetHubLedCommand {
+    /// The red channel, 0-255
+    #[arg(long, conflicts_with = "hex")]
+    red: Option<u8>,
+
+    /// The green channel, 0-255
+    #[arg(long, conflicts_with = "hex")]
+    green: Option<u8>,
+
+    /// The blue channel, 0-255
+    #[arg(long, conflicts_with = "hex")]
+    blue: Option<u8>,
+
+    /// The brightness, 0-100
+    #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+    brightness: Option<u8>,
+
+    /// Set the red, green and blue channels together,
+    /// in the form RRGGBB. A leading `#` is permitted.
+    #[arg(long, value_parser = parse_hex_color)]
+    hex: Option<(u8, u8, u8)>,
+
+    /// Turn the LED off by setting its brightness to 0
+    #[arg(long, conflicts_with = "brightness")]
+    off: bool,
+}
+
+fn parse_hex_color(s: &str) -> anyhow::Result<(u8, u8, u8)> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("expected a color in the form RRGGBB, but got {s}");
+    }
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16);
+    Ok((channel(0)?, channel(2)?, channel(4)?))
+}
+
+impl SetHubLedCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let before = hub.get_user_data().await?.color;
+
+        let (red, green, blue) = match self.hex {
+            Some(rgb) => rgb,
+            None => (
+                self.red.unwrap_or(before.red),
+                self.green.unwrap_or(before.green),
+                self.blue.unwrap_or(before.blue),
+            ),
+        };
+        let brightness = if self.off {
+            0
+        } else {
+            self.brightness.unwrap_or(before.brightness)
+        };
+
+        let after = hub
+            .set_led_color(Color {
+                red,
+                green,
+                blue,
+                brightness,
+            })
+            .await?
+            .color;
+
+        println!("Before: {before}");
+        println!("After:  {after}");
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use crate::api_types::{ShadeBatteryKind, ShadeData};
+
+/// Change the kind of power source that the hub believes a shade has.
+/// This is useful for correcting a hard wired shade that the hub
+/// has misclassified as being battery powered.
+#[derive(clap::Parser, Debug)]
+pub struct SetPowerSourceCommand {
+    /// The name or id of the shade
+    name: String,
+
+    /// The kind of power source to assign to the shade
+    #[arg(required_unless_present = "show", conflicts_with = "show")]
+    kind: Option<ShadeBatteryKind>,
+
+    /// Report the current power source without changing it
+    #[arg(long)]
+    show: bool,
+}
+
+fn print_battery_info(shade: &ShadeData) {
+    println!("{}: {}", shade.name(), shade.battery_kind.label());
+    println!("  Battery Status: {:?}", shade.battery_status);
+    match shade.battery_percent() {
+        Some(percent) => println!("  Battery Level:  {percent}%"),
+        None => println!("  Battery Level:  unavailable"),
+    }
+}
+
+impl SetPowerSourceCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let shade = hub.shade_by_id_or_name(&self.name).await?;
+
+        match self.kind {
+            Some(kind) => {
+                let shade = hub.change_battery_kind(shade.id, kind).await?;
+                print_battery_info(&shade);
+            }
+            None => print_battery_info(&shade),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use crate::api_types::ShadeUpdateMotion;
+use crate::postback::{postback_url, start_postback_server, PostbackEvent};
+use std::time::Duration;
+
+/// Show the home automation hook configuration of the hub;
+/// this is the url that the hub posts shade events to.
+#[derive(clap::Parser, Debug)]
+pub struct ShowAutomationHookCommand {
+    /// Verify the postback flow end-to-end by temporarily pointing
+    /// the hook at a listener started by this command, then jogging
+    /// the shade with this name or id and waiting for the hub to
+    /// report it. The original hook configuration is restored afterwards.
+    #[arg(long, value_name = "SHADE")]
+    test: Option<String>,
+
+    /// How many seconds to wait for the hub to post back when using --test
+    #[arg(long, default_value = "30", value_parser = crate::parse_duration)]
+    timeout: Duration,
+}
+
+impl ShowAutomationHookCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let hook = hub.get_home_automation_hook().await?;
+        println!("Enabled: {}", if hook.enabled { "yes" } else { "no" });
+        println!(
+            "URL:     {}",
+            hook.post_back_url.as_deref().unwrap_or("<none>")
+        );
+
+        let Some(shade_name) = &self.test else {
+            return Ok(());
+        };
+
+        let shade = hub.shade_by_id_or_name(shade_name).await?;
+        let serial = hub.get_user_data().await?.serial_number;
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<PostbackEvent>(32);
+        let port = start_postback_server(tx).await?;
+        let url = postback_url(hub.suggest_bind_address().await?, port, &serial);
+
+        println!();
+        println!("Testing via {url}");
+        hub.enable_home_automation_hook(&url).await?;
+
+        let result = async {
+            hub.move_shade(shade.id, ShadeUpdateMotion::Jog).await?;
+            tokio::time::timeout(self.timeout, async {
+                while let Some(event) = rx.recv().await {
+                    if event.serial == serial && !event.data.is_empty() {
+                        return Ok(());
+                    }
+                }
+                anyhow::bail!("postback server stopped unexpectedly");
+            })
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "no postback was received within {:?}. Check that the hub \
+                     is able to connect to this host on port {port}",
+                    self.timeout
+                )
+            })?
+        }
+        .await;
+
+        hub.restore_home_automation_hook(&hook).await?;
+
+        match result {
+            Ok(()) => {
+                println!("OK: received a postback from the hub");
+                Ok(())
+            }
+            Err(err) => {
+                println!("FAILED");
+                Err(err)
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::api_types::BatteryStatus;
+use std::collections::HashMap;
+use tabout::{Alignment, Column};
+
+/// Show the position, battery level and signal strength of every
+/// shade in a compact table, as a quick health check
+#[derive(clap::Parser, Debug)]
+pub struct StatusCommand {
+    /// Exit with a failure status if any battery powered shade
+    /// reports a battery level below this percentage.
+    /// Useful in monitoring scripts.
+    #[arg(long, value_name = "PCT")]
+    warn_battery_below: Option<u8>,
+}
+
+impl StatusCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let room_by_id: HashMap<_, _> = hub
+            .list_rooms()
+            .await?
+            .into_iter()
+            .map(|room| (room.id, room.name.to_string()))
+            .collect();
+
+        let mut shades: Vec<_> = hub
+            .list_shades(None, None)
+            .await?
+            .into_iter()
+            .map(|shade| {
+                let room = shade
+                    .room_id
+                    .and_then(|id| room_by_id.get(&id).cloned())
+                    .unwrap_or_default();
+                (room, shade)
+            })
+            .collect();
+        shades.sort_by(|(a_room, a), (b_room, b)| {
+            a_room.cmp(b_room).then_with(|| a.name().cmp(b.name()))
+        });
+
+        let columns = &[
+            Column {
+                name: "ROOM".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "SHADE".to_string(),
+                alignment: Alignment::Left,
+            },
+            Column {
+                name: "POSITION".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "BATTERY".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "SIGNAL".to_string(),
+                alignment: Alignment::Right,
+            },
+            Column {
+                name: "TIMED OUT".to_string(),
+                alignment: Alignment::Left,
+            },
+        ];
+
+        let mut low_battery = vec![];
+        let mut rows = vec![];
+        for (room, shade) in &shades {
+            let battery = match shade.battery_status {
+                BatteryStatus::PluggedIn => "Plugged In".to_string(),
+                _ => match shade.battery_percent() {
+                    Some(percent) => {
+                        if let Some(threshold) = self.warn_battery_below {
+                            if percent < threshold {
+                                low_battery.push(format!("{} ({percent}%)", shade.name()));
+                            }
+                        }
+                        format!("{percent}%")
+                    }
+                    None => String::new(),
+                },
+            };
+
+            rows.push(vec![
+                room.to_string(),
+                shade.name().to_string(),
+                shade
+                    .positions
+                    .as_ref()
+                    .map(|p| p.describe())
+                    .unwrap_or_default(),
+                battery,
+                shade
+                    .signal_strength_percent()
+                    .map(|s| format!("{s}%"))
+                    .unwrap_or_default(),
+                if shade.timed_out { "yes" } else { "" }.to_string(),
+            ]);
+        }
+
+        println!("{}", tabout::tabulate_output_as_string(columns, &rows)?);
+
+        if !low_battery.is_empty() {
+            anyhow::bail!(
+                "{} shade(s) have a battery level below {}%: {}",
+                low_battery.len(),
+                self.warn_battery_below.unwrap_or_default(),
+                low_battery.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use crate::api_types::{ShadeData, ShadeUpdateMotion};
+use crate::hub::Hub;
+use futures::stream::StreamExt;
+
+/// The number of move requests that we allow to be in flight at once
+const MAX_IN_FLIGHT: usize = 3;
+
+/// Stop all shades, or all shades in a room, from moving
+#[derive(clap::Parser, Debug)]
+pub struct StopAllCommand {
+    /// Only stop the shades in the room with this name or id
+    #[arg(long)]
+    room: Option<String>,
+}
+
+impl StopAllCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let room_id = match &self.room {
+            Some(name) => Some(hub.room_by_name(name).await?.id),
+            None => None,
+        };
+        let shades = hub.list_shades(None, room_id).await?;
+
+        let failed = move_all(&hub, &shades, ShadeUpdateMotion::Stop, "stopped").await;
+        if !failed.is_empty() {
+            anyhow::bail!("{} shade(s) could not be stopped", failed.len());
+        }
+        Ok(())
+    }
+}
+
+/// Apply `motion` to each of `shades`, printing the outcome for each
+/// as it completes. Returns the names of the shades that could not
+/// be moved.
+pub async fn move_all(
+    hub: &Hub,
+    shades: &[ShadeData],
+    motion: ShadeUpdateMotion,
+    past_tense: &str,
+) -> Vec<String> {
+    let mut results = futures::stream::iter(shades.iter())
+        .map(|shade| async move { (shade, hub.move_shade(shade.id, motion).await) })
+        .buffered(MAX_IN_FLIGHT)
+        .enumerate();
+
+    let mut failed = vec![];
+    while let Some((idx, (shade, result))) = results.next().await {
+        let progress = format!("[{}/{}] {}", idx + 1, shades.len(), shade.name());
+        match result {
+            Ok(_) => println!("{progress}: {past_tense}"),
+            Err(err) => {
+                println!("{progress}: failed: {err:#}");
+                failed.push(shade.name().to_string());
+            }
+        }
+    }
+    failed
+}
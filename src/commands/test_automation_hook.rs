@@ -0,0 +1,23 @@
+/// Check that the home automation postback url configured in the hub
+/// is reachable. Note that the test request is sent from this host,
+/// so it cannot detect firewall rules that only affect the hub.
+#[derive(clap::Parser, Debug)]
+pub struct TestAutomationHookCommand {}
+
+impl TestAutomationHookCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+
+        let (url, outcome) = hub.test_home_automation_hook().await?;
+        match outcome {
+            Ok(()) => {
+                println!("OK: {url} is reachable");
+                Ok(())
+            }
+            Err(err) => {
+                println!("FAILED: {url}");
+                Err(err)
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::api_types::{HomeAutomationPostBackData, HomeAutomationService, ShadeData};
+use crate::hub::Hub;
+use crate::postback::{postback_url, start_postback_server, PostbackEvent};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Print shade position updates as they are reported by the hub.
+/// The home automation hook configuration that was in place before
+/// we started, such as the url used by a running serve-mqtt, is
+/// restored when this command exits.
+#[derive(clap::Parser, Debug)]
+pub struct WatchShadesCommand {
+    /// Emit each update as a line of json
+    #[arg(long)]
+    json: bool,
+
+    /// Instead of restoring the previous home automation hook
+    /// configuration on exit, disable the hook.
+    #[arg(long)]
+    disable_hook: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct JsonUpdate<'a> {
+    timestamp: String,
+    shade_name: &'a str,
+    #[serde(flatten)]
+    item: &'a HomeAutomationPostBackData,
+}
+
+async fn shades_by_id(hub: &Hub) -> anyhow::Result<HashMap<i32, ShadeData>> {
+    Ok(hub
+        .list_shades(None, None)
+        .await?
+        .into_iter()
+        .map(|shade| (shade.id, shade))
+        .collect())
+}
+
+async fn enable_hook(hub: &Hub, port: u16, serial: &str) -> anyhow::Result<()> {
+    let addr = hub.suggest_bind_address().await?;
+    hub.enable_home_automation_hook(&postback_url(addr, port, serial))
+        .await
+}
+
+impl WatchShadesCommand {
+    pub async fn run(&self, args: &crate::Args) -> anyhow::Result<()> {
+        let hub = args.hub().await?;
+        let serial = hub.get_user_data().await?.serial_number;
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<PostbackEvent>(32);
+        let port = start_postback_server(tx).await?;
+
+        let mut shades = shades_by_id(&hub).await?;
+        let previous_hook = hub.get_home_automation_hook().await?;
+        let result = async {
+            enable_hook(&hub, port, &serial).await?;
+            log::info!("Waiting for updates from the hub");
+
+            let mut responding = true;
+            let mut health_check = tokio::time::interval(Duration::from_secs(60));
+            // The first tick completes immediately
+            health_check.tick().await;
+
+            let shutdown = crate::shutdown_signal();
+            tokio::pin!(shutdown);
+
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => {
+                        log::info!("Shutting down");
+                        break;
+                    }
+                    event = rx.recv() => {
+                        let Some(PostbackEvent { serial: event_serial, mut data, .. }) = event else {
+                            break;
+                        };
+                        if event_serial != serial {
+                            log::warn!("ignoring postback for serial={event_serial}");
+                            continue;
+                        }
+                        data.sort_by_key(|item| item.record_type);
+                        for item in &data {
+                            self.print_update(&shades, item)?;
+                        }
+                    }
+                    _ = health_check.tick() => {
+                        match hub.get_user_data().await {
+                            Ok(_) if !responding => {
+                                // The hub may have been restarted and forgotten
+                                // about our hook, so register it again
+                                log::info!("Hub is responding again");
+                                match enable_hook(&hub, port, &serial).await {
+                                    Ok(()) => {
+                                        responding = true;
+                                        if let Ok(updated) = shades_by_id(&hub).await {
+                                            shades = updated;
+                                        }
+                                    }
+                                    Err(err) => {
+                                        log::error!("Re-registering home automation hook: {err:#}");
+                                    }
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(err) => {
+                                if responding {
+                                    log::warn!("Hub is not responding: {err:#}");
+                                }
+                                responding = false;
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        if self.disable_hook {
+            hub.disable_home_automation_hook().await?;
+        } else {
+            hub.restore_home_automation_hook(&previous_hook).await?;
+        }
+
+        result
+    }
+
+    fn print_update(
+        &self,
+        shades: &HashMap<i32, ShadeData>,
+        item: &HomeAutomationPostBackData,
+    ) -> anyhow::Result<()> {
+        let shade_name = match (shades.get(&item.shade_id), item.service) {
+            (Some(shade), HomeAutomationService::Primary) => shade.name().to_string(),
+            (Some(shade), HomeAutomationService::Secondary) => shade.secondary_name(),
+            (None, _) => item.shade_id.to_string(),
+        };
+        let now = Utc::now().with_timezone(&crate::resolve_timezone());
+
+        if self.json {
+            let update = JsonUpdate {
+                timestamp: now.to_rfc3339(),
+                shade_name: &shade_name,
+                item,
+            };
+            println!("{}", serde_json::to_string(&update)?);
+            return Ok(());
+        }
+
+        let mut positions = vec![];
+        for (label, pos) in [
+            ("current", item.current_position),
+            ("target", item.target_position),
+            ("stopped", item.stopped_position),
+        ] {
+            if let Some(pos) = pos {
+                positions.push(format!("{label}={pos}%"));
+            }
+        }
+
+        if let Some(duration) = item.duration_ms {
+            positions.push(format!("duration={duration}ms"));
+        }
+        if let Some(remaining) = item.remaining_duration_ms {
+            positions.push(format!("remaining={remaining}ms"));
+        }
+
+        println!(
+            "{} {shade_name} {:?} {:?} {}",
+            now.format("%Y-%m-%dT%H:%M:%S"),
+            item.service,
+            item.record_type,
+            positions.join(" ")
+        );
+        Ok(())
+    }
+}
@@ -2,32 +2,98 @@ use crate::api_types::UserData;
 use crate::hub::Hub;
 use anyhow::Context;
 use std::net::IpAddr;
+use std::sync::OnceLock;
 use std::time::Duration;
 use tokio::sync::mpsc::Receiver;
 use wez_mdns::{QueryParameters, RecordKind};
 
 pub const POWERVIEW_SERVICE: &str = "_powerview._tcp.local";
 
+/// The addresses of the interface that discovery is constrained to,
+/// if any.  See set_discovery_interface.
+static DISCOVERY_INTERFACE: OnceLock<Vec<if_addrs::IfAddr>> = OnceLock::new();
+
+/// Constrain discovery to hubs that are reachable via the network
+/// interface identified by `spec`, which may be either an interface
+/// name such as `eth0`, or one of the addresses assigned to it.
+///
+/// The underlying mDNS implementation listens on all interfaces and
+/// doesn't allow binding its socket to a specific NIC, so rather than
+/// restricting where the query is sent, we ignore any responses that
+/// advertise an address outside of the subnet(s) of the interface.
+pub fn set_discovery_interface(spec: &str) -> anyhow::Result<()> {
+    let source: Option<IpAddr> = spec.parse().ok();
+    let interfaces = if_addrs::get_if_addrs().context("listing network interfaces")?;
+    let name = interfaces
+        .iter()
+        .find(|iface| iface.name == spec || Some(iface.ip()) == source)
+        .map(|iface| iface.name.clone())
+        .ok_or_else(|| anyhow::anyhow!("No network interface matches {spec}"))?;
+
+    let addrs: Vec<if_addrs::IfAddr> = interfaces
+        .into_iter()
+        .filter(|iface| iface.name == name)
+        .map(|iface| iface.addr)
+        .collect();
+    log::debug!("Constraining discovery to {name}: {addrs:?}");
+
+    DISCOVERY_INTERFACE
+        .set(addrs)
+        .map_err(|_| anyhow::anyhow!("discovery interface was already set"))
+}
+
+/// Returns true if `addr` is on the same subnet as one of the
+/// addresses of the interface passed to set_discovery_interface,
+/// or if no interface constraint was configured.
+fn reachable_via_discovery_interface(addr: IpAddr) -> bool {
+    let Some(iface_addrs) = DISCOVERY_INTERFACE.get() else {
+        return true;
+    };
+
+    iface_addrs.iter().any(|iface| match (iface, addr) {
+        (if_addrs::IfAddr::V4(v4), IpAddr::V4(addr)) => {
+            let mask = u32::from(v4.netmask);
+            u32::from(v4.ip) & mask == u32::from(addr) & mask
+        }
+        (if_addrs::IfAddr::V6(v6), IpAddr::V6(addr)) => {
+            let mask = u128::from(v6.netmask);
+            u128::from(v6.ip) & mask == u128::from(addr) & mask
+        }
+        _ => false,
+    })
+}
+
 fn ip_from_response(response: wez_mdns::Response) -> anyhow::Result<IpAddr> {
     let mut ipv4 = None;
     let mut ipv6 = None;
+    let mut unreachable = vec![];
 
     for record in &response.additional {
-        match record.kind {
-            RecordKind::A(v4) => {
-                ipv4.replace(v4);
-            }
-            RecordKind::AAAA(v6) => {
-                ipv6.replace(v6);
-            }
-            _ => {}
+        let addr: IpAddr = match record.kind {
+            RecordKind::A(v4) => v4.into(),
+            RecordKind::AAAA(v6) => v6.into(),
+            _ => continue,
+        };
+        if !reachable_via_discovery_interface(addr) {
+            unreachable.push(addr);
+            continue;
+        }
+        if addr.is_ipv4() {
+            ipv4.replace(addr);
+        } else {
+            ipv6.replace(addr);
         }
     }
 
     if let Some(v4) = ipv4 {
-        Ok(v4.into())
+        Ok(v4)
     } else if let Some(v6) = ipv6 {
-        Ok(v6.into())
+        Ok(v6)
+    } else if !unreachable.is_empty() {
+        anyhow::bail!(
+            "Ignoring hub at {unreachable:?} because it is not \
+             reachable via the discovery interface"
+        );
     } else {
         anyhow::bail!(
             "Response didn't include either a v4 or v6 address for the hub. {response:?}"
@@ -61,6 +127,42 @@ pub async fn resolve_hub(timeout: Duration) -> anyhow::Result<IpAddr> {
     );
 }
 
+/// Run `discover`, retrying up to `retries` more times after it fails,
+/// sleeping for `delay` between attempts, returning the first success
+/// or the final error.
+/// `discover` is passed the timeout for the attempt, which starts at
+/// `timeout` and doubles after each failure, to give slow or flaky
+/// networks more of a chance to respond.
+pub async fn retry_discovery<T, F, Fut>(
+    retries: usize,
+    delay: Duration,
+    timeout: Duration,
+    mut discover: F,
+) -> anyhow::Result<T>
+where
+    F: FnMut(Duration) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<T>>,
+{
+    let attempts = retries + 1;
+    let mut attempt = 1;
+    let mut timeout = timeout;
+    loop {
+        if attempts > 1 {
+            log::info!("Discovery attempt {attempt} of {attempts}, timeout {timeout:?}");
+        }
+        match discover(timeout).await {
+            Ok(result) => return Ok(result),
+            Err(err) if attempt < attempts => {
+                log::warn!("Discovery attempt {attempt} of {attempts} failed: {err:#}");
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+                timeout *= 2;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ResolvedHub {
     pub hub: Hub,
@@ -69,7 +171,7 @@ pub struct ResolvedHub {
 
 impl ResolvedHub {
     async fn new(addr: IpAddr) -> Self {
-        let hub = Hub::with_addr(addr);
+        let hub = Hub::connect(addr).await;
         Self::with_hub(hub).await
     }
 
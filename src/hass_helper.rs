@@ -1,24 +1,84 @@
 use crate::version_info::pview_version;
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 
 const MODEL: &str = "pv2mqtt";
 const URL: &str = "https://github.com/wez/pview";
 
-#[derive(Serialize, Clone, Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct EntityConfig {
     pub availability_topic: String,
+    /// Additional availability topics, all of which must be online
+    /// for the entity to be considered available
+    pub availability: Vec<String>,
     pub name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub device_class: Option<String>,
     pub origin: Origin,
     pub device: Device,
     pub unique_id: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub entity_category: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
 }
 
+impl Serialize for EntityConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Availability<'a> {
+            topic: &'a str,
+        }
+
+        // hass rejects configs that specify both availability_topic
+        // and availability, so when there are additional topics,
+        // availability_topic is folded into the availability list
+        #[derive(Serialize)]
+        struct Repr<'a> {
+            #[serde(skip_serializing_if = "Option::is_none")]
+            availability_topic: Option<&'a str>,
+            #[serde(skip_serializing_if = "Vec::is_empty")]
+            availability: Vec<Availability<'a>>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            availability_mode: Option<&'static str>,
+            name: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            device_class: Option<&'a str>,
+            origin: &'a Origin,
+            device: &'a Device,
+            unique_id: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            entity_category: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            icon: Option<&'a str>,
+        }
+
+        let (availability_topic, availability, availability_mode) = if self.availability.is_empty()
+        {
+            (Some(self.availability_topic.as_str()), vec![], None)
+        } else {
+            (
+                None,
+                std::iter::once(&self.availability_topic)
+                    .chain(self.availability.iter())
+                    .map(|topic| Availability { topic })
+                    .collect(),
+                Some("all"),
+            )
+        };
+
+        Repr {
+            availability_topic,
+            availability,
+            availability_mode,
+            name: self.name.as_deref(),
+            device_class: self.device_class.as_deref(),
+            origin: &self.origin,
+            device: &self.device,
+            unique_id: &self.unique_id,
+            entity_category: self.entity_category.as_deref(),
+            icon: self.icon.as_deref(),
+        }
+        .serialize(serializer)
+    }
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct Origin {
     pub name: &'static str,
@@ -58,10 +118,32 @@ pub struct CoverConfig {
     #[serde(flatten)]
     pub base: EntityConfig,
 
-    pub state_topic: String,
-    pub position_topic: String,
-    pub set_position_topic: String,
-    pub command_topic: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_position_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tilt_status_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tilt_command_topic: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tilt_min: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tilt_max: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_open: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_close: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_stop: Option<String>,
+    /// When true, hass assumes that the cover reached the
+    /// commanded position rather than waiting to be told
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub optimistic: Option<bool>,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -101,3 +183,71 @@ pub struct SelectConfig {
     pub options: Vec<String>,
     pub state_topic: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entity(availability: Vec<String>) -> EntityConfig {
+        EntityConfig {
+            availability_topic: "pv2mqtt/shade/1/availability".to_string(),
+            availability,
+            name: Some("Kitchen".to_string()),
+            device_class: Some("shade".to_string()),
+            unique_id: "1-shade".to_string(),
+            entity_category: Some("diagnostic".to_string()),
+            icon: Some("mdi:blinds".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn expected(availability: serde_json::Value) -> serde_json::Value {
+        let mut expected = json!({
+            "name": "Kitchen",
+            "device_class": "shade",
+            "origin": {
+                "name": MODEL,
+                "sw_version": pview_version(),
+                "url": URL,
+            },
+            "device": {
+                "name": "",
+                "manufacturer": "",
+                "model": "",
+            },
+            "unique_id": "1-shade",
+            "entity_category": "diagnostic",
+            "icon": "mdi:blinds",
+        });
+        expected
+            .as_object_mut()
+            .unwrap()
+            .extend(availability.as_object().unwrap().clone());
+        expected
+    }
+
+    #[test]
+    fn single_availability_topic() {
+        assert_eq!(
+            serde_json::to_value(entity(vec![])).unwrap(),
+            expected(json!({
+                "availability_topic": "pv2mqtt/shade/1/availability",
+            }))
+        );
+    }
+
+    #[test]
+    fn multiple_availability_topics() {
+        assert_eq!(
+            serde_json::to_value(entity(vec!["pv2mqtt/bridge/availability".to_string()])).unwrap(),
+            expected(json!({
+                "availability": [
+                    {"topic": "pv2mqtt/shade/1/availability"},
+                    {"topic": "pv2mqtt/bridge/availability"},
+                ],
+                "availability_mode": "all",
+            }))
+        );
+    }
+}
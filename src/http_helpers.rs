@@ -1,105 +1,369 @@
 use anyhow::Context;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
 use std::time::Duration;
 use thiserror::Error;
 
+use reqwest::header::HeaderMap;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+static LENIENT: AtomicBool = AtomicBool::new(false);
+static REQUEST_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Enable or disable lenient parsing of hub responses.
+/// When enabled, fields that are not known to our api types are
+/// logged and discarded, rather than causing parsing to fail.
+pub fn set_lenient(lenient: bool) {
+    LENIENT.store(lenient, Ordering::Relaxed);
+}
+
+/// Configure the timeout applied to each request made to the hub.
+/// This must be called before the first request is made in order
+/// to take effect.
+pub fn set_request_timeout(timeout: Duration) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder().timeout(timeout).build()?;
+    CLIENT
+        .set(client)
+        .map_err(|_| anyhow::anyhow!("request timeout was already configured"))
+}
+
+/// Returns the client shared by all hub requests
+fn client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(DEFAULT_REQUEST_TIMEOUT)
+            .build()
+            .expect("failed to build http client")
+    })
+}
+
+/// The url of a resource on the hub, along with any headers, such as
+/// credentials, that must accompany requests made to it
+#[derive(Clone, Debug)]
+pub struct HubRequest {
+    url: String,
+    headers: HeaderMap,
+    /// When set, a permit must be held while the request is in flight
+    limiter: Option<Arc<Semaphore>>,
+    /// Overrides the configured request timeout
+    timeout: Option<Duration>,
+}
+
+impl HubRequest {
+    pub fn new(url: String, headers: HeaderMap) -> Self {
+        Self {
+            url,
+            headers,
+            limiter: None,
+            timeout: None,
+        }
+    }
+
+    /// Use `timeout` for this request, rather than the configured
+    /// request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout.replace(timeout);
+        self
+    }
+
+    /// Limit the number of concurrent requests made via `limiter`
+    pub fn with_limiter(mut self, limiter: Arc<Semaphore>) -> Self {
+        self.limiter.replace(limiter);
+        self
+    }
+
+    /// Wait until the limiter permits the request to be made.
+    /// The returned permit must be held until the response body
+    /// has been read.
+    async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.limiter {
+            // The semaphore is never closed, so this cannot fail
+            Some(limiter) => Arc::clone(limiter).acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+
+    /// Describes a request to this url, for use in error context
+    fn describe(&self, method: &reqwest::Method) -> String {
+        format!("{method} {}", self.url)
+    }
+
+    fn build(self, method: reqwest::Method) -> reqwest::RequestBuilder {
+        let builder = client().request(method, self.url).headers(self.headers);
+        match self.timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+}
+
+impl From<String> for HubRequest {
+    fn from(url: String) -> Self {
+        Self::new(url, HeaderMap::new())
+    }
+}
+
+/// Errors that can arise while talking to the hub.
+/// These are returned inside an anyhow::Error, so callers that care
+/// about the specific kind of failure should walk the error chain
+/// and `downcast_ref::<HubError>()` to find it.
 #[derive(Error, Debug)]
-#[error("Hub is Locked for maintenance. Response: {body}")]
-pub struct LockedError {
-    pub body: String,
+pub enum HubError {
+    #[error("unable to communicate with the hub: {0:#}")]
+    Unreachable(#[source] reqwest::Error),
+    #[error("Hub is Locked for maintenance. Response: {body}")]
+    Locked { body: String },
+    #[error("{0}")]
+    NotFound(String),
+    #[error("request status {code}: {reason}. Response body: {body}")]
+    BadStatus {
+        code: u16,
+        reason: String,
+        body: String,
+    },
+    #[error("parsing response as json: {body}")]
+    Deserialize {
+        #[source]
+        source: serde_json::Error,
+        body: String,
+    },
+}
+
+impl HubError {
+    /// Returns true if this error indicates that the hub is not
+    /// currently able to service requests, as opposed to rejecting
+    /// a specific request.
+    pub fn is_unresponsive(&self) -> bool {
+        matches!(self, Self::Unreachable(_) | Self::Locked { .. })
+    }
 }
 
 pub async fn json_body<T: serde::de::DeserializeOwned>(
     response: reqwest::Response,
-) -> anyhow::Result<T> {
-    let data = response.bytes().await.context("ready response body")?;
-    serde_json::from_slice(&data).with_context(|| {
-        format!(
-            "parsing response as json: {}",
-            String::from_utf8_lossy(&data)
-        )
-    })
+) -> Result<T, HubError> {
+    let data = response.bytes().await.map_err(HubError::Unreachable)?;
+    match serde_json::from_slice(&data) {
+        Ok(result) => Ok(result),
+        Err(source) => {
+            if LENIENT.load(Ordering::Relaxed) {
+                if let Ok(value) = serde_json::from_slice(&data) {
+                    if let Some(result) = lenient_deserialize(value) {
+                        return Ok(result);
+                    }
+                }
+            }
+            Err(HubError::Deserialize {
+                source,
+                body: String::from_utf8_lossy(&data).to_string(),
+            })
+        }
+    }
 }
 
-pub async fn get_request_with_json_response<T: reqwest::IntoUrl, R: serde::de::DeserializeOwned>(
-    url: T,
-) -> anyhow::Result<R> {
-    let response = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()?
-        .request(reqwest::Method::GET, url)
-        .send()
-        .await?;
+/// Repeatedly attempt to deserialize `value`, removing any unknown
+/// fields that are reported along the way.
+/// Returns None if parsing fails for some other reason.
+fn lenient_deserialize<T: serde::de::DeserializeOwned>(mut value: serde_json::Value) -> Option<T> {
+    // Bound the work we do for a pathological response
+    for _ in 0..1000 {
+        let err = match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(result) => return Some(result),
+            Err(err) => err,
+        };
 
-    let status = response.status();
-    if !status.is_success() {
-        let url = response.url().clone();
-        let body_bytes = response.bytes().await.with_context(|| {
-            format!(
-                "request status {}: {}, and failed to read response body",
-                status.as_u16(),
-                status.canonical_reason().unwrap_or("")
-            )
-        })?;
-
-        if status.as_u16() == 423 {
-            let body = String::from_utf8_lossy(&body_bytes).to_string();
-            return Err(LockedError { body }).with_context(move || format!("GET {url}"));
+        let message = err.inner().to_string();
+        let field = message
+            .strip_prefix("unknown field `")?
+            .split('`')
+            .next()?
+            .to_string();
+
+        let path = err.path().to_string();
+        log::warn!("Ignoring unknown field {path} in hub response");
+
+        // Walk down to the object that holds the unknown field
+        let mut object = &mut value;
+        let segments: Vec<_> = err.path().iter().cloned().collect();
+        for segment in &segments[..segments.len().saturating_sub(1)] {
+            object = match segment {
+                serde_path_to_error::Segment::Map { key } => object.get_mut(key.as_str())?,
+                serde_path_to_error::Segment::Seq { index } => object.get_mut(*index)?,
+                _ => return None,
+            };
         }
+        object.as_object_mut()?.remove(&field)?;
+    }
+    None
+}
 
-        anyhow::bail!(
-            "request status {}: {}. Response body: {}",
-            status.as_u16(),
-            status.canonical_reason().unwrap_or(""),
-            String::from_utf8_lossy(&body_bytes)
-        );
+/// Returns the number of requests to the hub that have failed,
+/// either because the hub was unreachable or returned an error status
+pub fn request_failure_count() -> u64 {
+    REQUEST_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Send the request and map any non-success status into
+/// the appropriate HubError
+async fn send_request(request: reqwest::RequestBuilder) -> Result<reqwest::Response, HubError> {
+    let result = send_request_impl(request).await;
+    if result.is_err() {
+        REQUEST_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+    result
+}
+
+async fn send_request_impl(
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response, HubError> {
+    let response = request.send().await.map_err(HubError::Unreachable)?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
     }
-    json_body(response).await.with_context(|| {
-        format!(
-            "request status {}: {}",
-            status.as_u16(),
-            status.canonical_reason().unwrap_or("")
-        )
+
+    let code = status.as_u16();
+    let reason = status.canonical_reason().unwrap_or("").to_string();
+    let body_bytes = response.bytes().await.map_err(HubError::Unreachable)?;
+    let body = String::from_utf8_lossy(&body_bytes).to_string();
+
+    Err(match code {
+        423 => HubError::Locked { body },
+        404 => HubError::NotFound(format!(
+            "request status {code}: {reason}. Response body: {body}"
+        )),
+        _ => HubError::BadStatus { code, reason, body },
     })
 }
 
+pub async fn get_request_with_json_response<T: Into<HubRequest>, R: serde::de::DeserializeOwned>(
+    target: T,
+) -> anyhow::Result<R> {
+    let target = target.into();
+    let context = target.describe(&reqwest::Method::GET);
+    let _permit = target.acquire().await;
+    async {
+        let response = send_request(target.build(reqwest::Method::GET)).await?;
+        json_body(response).await
+    }
+    .await
+    .context(context)
+}
+
+/// Fetch the url and return the json body without mapping it
+/// through any of our api types, which is helpful when diagnosing
+/// deserialization problems.
+pub async fn get_request_raw_json<T: Into<HubRequest>>(
+    target: T,
+) -> anyhow::Result<serde_json::Value> {
+    get_request_with_json_response(target).await
+}
+
 pub async fn request_with_json_response<
-    T: reqwest::IntoUrl,
+    T: Into<HubRequest>,
     B: serde::Serialize,
     R: serde::de::DeserializeOwned,
 >(
     method: reqwest::Method,
-    url: T,
+    target: T,
     body: &B,
 ) -> anyhow::Result<R> {
-    let response = reqwest::Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()?
-        .request(method, url)
-        .json(body)
-        .send()
-        .await?;
+    let target = target.into();
+    let context = target.describe(&method);
+    let _permit = target.acquire().await;
+    async {
+        let response = send_request(target.build(method).json(body)).await?;
+        json_body(response).await
+    }
+    .await
+    .context(context)
+}
 
-    let status = response.status();
-    if !status.is_success() {
-        let body_bytes = response.bytes().await.with_context(|| {
-            format!(
-                "request status {}: {}, and failed to read response body",
-                status.as_u16(),
-                status.canonical_reason().unwrap_or("")
-            )
-        })?;
-        anyhow::bail!(
-            "request status {}: {}. Response body: {}",
-            status.as_u16(),
-            status.canonical_reason().unwrap_or(""),
-            String::from_utf8_lossy(&body_bytes)
+/// POST a plain text body to the url, ignoring any response body
+pub async fn post_text_request<T: reqwest::IntoUrl>(url: T, body: String) -> anyhow::Result<()> {
+    send_request(client().request(reqwest::Method::POST, url).body(body)).await?;
+    Ok(())
+}
+
+/// Issue a DELETE request. The hub responds with 204 No Content on
+/// success, so there is no body to decode.
+pub async fn delete_request<T: Into<HubRequest>>(target: T) -> anyhow::Result<()> {
+    let target = target.into();
+    let context = target.describe(&reqwest::Method::DELETE);
+    let _permit = target.acquire().await;
+    send_request(target.build(reqwest::Method::DELETE))
+        .await
+        .context(context)?;
+    Ok(())
+}
+
+/// Issue a POST request with no body, ignoring any response body.
+pub async fn post_request<T: Into<HubRequest>>(target: T) -> anyhow::Result<()> {
+    let target = target.into();
+    let context = target.describe(&reqwest::Method::POST);
+    let _permit = target.acquire().await;
+    send_request(target.build(reqwest::Method::POST))
+        .await
+        .context(context)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    struct Shade {
+        id: i32,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "camelCase", deny_unknown_fields)]
+    struct Response {
+        shade_ids: Vec<i32>,
+        shade_data: Vec<Shade>,
+    }
+
+    #[test]
+    fn lenient_top_level_unknown_field() {
+        let value = json!({"shadeIds": [1], "shadeData": [], "foo": true});
+        assert_eq!(
+            lenient_deserialize::<Response>(value),
+            Some(Response {
+                shade_ids: vec![1],
+                shade_data: vec![],
+            })
         );
     }
-    json_body(response).await.with_context(|| {
-        format!(
-            "request status {}: {}",
-            status.as_u16(),
-            status.canonical_reason().unwrap_or("")
-        )
-    })
+
+    #[test]
+    fn lenient_nested_unknown_field() {
+        let value = json!({
+            "shadeIds": [1, 2],
+            "shadeData": [{"id": 1, "foo": 1}, {"id": 2, "bar": 2}],
+        });
+        assert_eq!(
+            lenient_deserialize::<Response>(value),
+            Some(Response {
+                shade_ids: vec![1, 2],
+                shade_data: vec![Shade { id: 1 }, Shade { id: 2 }],
+            })
+        );
+    }
+
+    #[test]
+    fn lenient_other_errors() {
+        let value = json!({"shadeIds": ["one"], "shadeData": [], "foo": true});
+        assert_eq!(lenient_deserialize::<Response>(value), None);
+
+        let value = json!({"shadeData": []});
+        assert_eq!(lenient_deserialize::<Response>(value), None);
+    }
 }
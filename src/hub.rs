@@ -1,39 +1,290 @@
 use crate::api_types::*;
 use crate::discovery::resolve_hub;
-use crate::http_helpers::{get_request_with_json_response, request_with_json_response};
+use crate::http_helpers::{
+    delete_request, get_request_raw_json, get_request_with_json_response, post_request,
+    post_text_request, request_with_json_response, HubError, HubRequest,
+};
 use anyhow::Context;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::Method;
 use serde::Deserialize;
 use serde_json::json;
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+
+/// The generation of the PowerView API spoken by a hub.
+/// Only the url scheme differs between the two here: Gen3 hubs also
+/// return differently shaped json, which the types in api_types.rs
+/// don't understand, so most commands will fail to parse the
+/// responses from a Gen3 hub.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HubGeneration {
+    Gen2,
+    Gen3,
+}
+
+/// How long to wait for each generation to respond when probing
+const DETECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The generations previously detected for each hub address
+static DETECTED_GENERATIONS: Mutex<Option<HashMap<IpAddr, HubGeneration>>> = Mutex::new(None);
+
+/// Maps the resources that we use onto the url scheme used
+/// by a particular generation of the PowerView API
+pub trait HubApi: Send + Sync {
+    /// Returns the path, relative to the root of the hub, for
+    /// the specified resource. `resource` is expressed in terms
+    /// of the Gen2 API, without the `api/` prefix.
+    fn resource_path(&self, resource: &str) -> String;
+}
+
+struct Gen2Api;
+
+impl HubApi for Gen2Api {
+    fn resource_path(&self, resource: &str) -> String {
+        format!("api/{resource}")
+    }
+}
+
+struct Gen3Api;
+
+impl HubApi for Gen3Api {
+    fn resource_path(&self, resource: &str) -> String {
+        // Gen3 moved the hub level information under gateway,
+        // while everything else lives under home
+        match resource.strip_prefix("userdata") {
+            Some(rest) => format!("gateway{rest}"),
+            None => format!("home/{resource}"),
+        }
+    }
+}
+
+impl HubGeneration {
+    pub fn api(self) -> &'static dyn HubApi {
+        match self {
+            Self::Gen2 => &Gen2Api,
+            Self::Gen3 => &Gen3Api,
+        }
+    }
+
+    /// Probe the hub to figure out which generation of the API it speaks.
+    /// Defaults to Gen2 if the hub doesn't respond to either.
+    /// The result is remembered for each address, so that the hub
+    /// is probed only once.
+    pub async fn detect(addr: IpAddr, headers: &HeaderMap) -> Self {
+        if let Some(generation) = DETECTED_GENERATIONS
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|detected| detected.get(&addr))
+        {
+            return *generation;
+        }
+
+        for generation in [Self::Gen2, Self::Gen3] {
+            let url = hub_url(addr, &generation.api().resource_path("userdata"));
+            let request = HubRequest::new(url, headers.clone()).with_timeout(DETECT_TIMEOUT);
+            if get_request_raw_json(request).await.is_ok() {
+                DETECTED_GENERATIONS
+                    .lock()
+                    .unwrap()
+                    .get_or_insert_with(HashMap::new)
+                    .insert(addr, generation);
+                return generation;
+            }
+        }
+        // Don't remember this; the hub may simply have been unavailable
+        Self::Gen2
+    }
+}
+
+/// Build the url for `path` on the hub at `addr`.
+/// IPv6 addresses must be enclosed in brackets to be valid in a url.
+fn hub_url(addr: IpAddr, path: &str) -> String {
+    match addr {
+        IpAddr::V4(addr) => format!("http://{addr}/{path}"),
+        IpAddr::V6(addr) => format!("http://[{addr}]/{path}"),
+    }
+}
+
+/// Bump this when the layout of the `Hub::export_config` document
+/// changes, so that a restore can tell how to interpret it
+const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// The number of requests that each Hub may have in flight at once,
+/// or 0 if there is no limit
+static REQUEST_CONCURRENCY: AtomicUsize = AtomicUsize::new(0);
+
+/// Configure the number of requests that a Hub may have in flight at
+/// once. This applies to hubs created after it is called.
+pub fn set_request_concurrency(limit: usize) {
+    REQUEST_CONCURRENCY.store(limit.max(1), Ordering::Relaxed);
+}
+
+/// Sent along with every request to the hub, including those
+/// made while probing and discovering it
+static HUB_HEADERS: OnceLock<HeaderMap> = OnceLock::new();
+
+/// Send `token` as a bearer token with every request to the hub.
+/// Current Gen2 hubs don't require this, but some newer firmware
+/// does. This must be called before connecting to the hub in order
+/// to take effect.
+pub fn set_hub_token(token: &str) -> anyhow::Result<()> {
+    let mut value = HeaderValue::from_str(&format!("Bearer {token}"))
+        .context("hub token contains characters that are not valid in a header")?;
+    value.set_sensitive(true);
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, value);
+    HUB_HEADERS
+        .set(headers)
+        .map_err(|_| anyhow::anyhow!("hub token was already configured"))
+}
+
+fn hub_headers() -> HeaderMap {
+    HUB_HEADERS.get().cloned().unwrap_or_default()
+}
 
 #[derive(Debug, Clone)]
 pub struct Hub {
     addr: IpAddr,
+    generation: HubGeneration,
+    /// Sent along with every request; used to carry credentials
+    /// for hubs that require them
+    headers: HeaderMap,
+    /// Limits the number of concurrent requests, so that a burst
+    /// of activity doesn't swamp the hub. Shared between clones.
+    limiter: Option<Arc<Semaphore>>,
 }
 
 impl Hub {
-    fn url(&self, extra: &str) -> String {
-        format!("http://{}/{extra}", self.addr)
+    fn url(&self, resource: &str) -> HubRequest {
+        let request = HubRequest::new(
+            hub_url(self.addr, &self.generation.api().resource_path(resource)),
+            self.headers.clone(),
+        );
+        match &self.limiter {
+            Some(limiter) => request.with_limiter(Arc::clone(limiter)),
+            None => request,
+        }
     }
 
     pub fn addr(&self) -> IpAddr {
         self.addr
     }
 
+    pub fn generation(&self) -> HubGeneration {
+        self.generation
+    }
+
     pub async fn list_rooms(&self) -> anyhow::Result<Vec<RoomData>> {
-        let mut resp: RoomResponse = get_request_with_json_response(self.url("api/rooms")).await?;
+        let mut resp: RoomResponse = get_request_with_json_response(self.url("rooms")).await?;
         resp.room_data
             .sort_by_key(|item| (item.order, item.name.to_string()));
         Ok(resp.room_data)
     }
 
     pub async fn list_scenes(&self) -> anyhow::Result<Vec<Scene>> {
+        let mut resp: ScenesResponse = get_request_with_json_response(self.url("scenes")).await?;
+        resp.scene_data
+            .sort_by_key(|item| (item.order, item.name.clone()));
+
+        Ok(resp.scene_data)
+    }
+
+    pub async fn list_scene_collections(&self) -> anyhow::Result<Vec<SceneCollection>> {
+        let mut resp: SceneCollectionsResponse =
+            get_request_with_json_response(self.url("scenecollections")).await?;
+        resp.scene_collection_data
+            .sort_by_key(|item| (item.order, item.name.clone()));
+
+        Ok(resp.scene_collection_data)
+    }
+
+    /// Returns the members of all scene collections, keyed by
+    /// the scene collection id
+    pub async fn list_scene_collection_members(
+        &self,
+    ) -> anyhow::Result<HashMap<i32, Vec<SceneCollectionMember>>> {
+        let resp: SceneCollectionMembersResponse =
+            get_request_with_json_response(self.url("scenecollectionmembers")).await?;
+
+        let mut by_collection = HashMap::new();
+        for member in resp.scene_collection_member_data {
+            by_collection
+                .entry(member.scene_collection_id)
+                .or_insert_with(Vec::new)
+                .push(member);
+        }
+
+        Ok(by_collection)
+    }
+
+    pub async fn scene_collection_by_name(&self, name: &str) -> anyhow::Result<SceneCollection> {
+        let collections = self.list_scene_collections().await?;
+        for c in collections {
+            if c.name.eq_ignore_ascii_case(name) {
+                return Ok(c);
+            }
+            if c.id.to_string() == name {
+                return Ok(c);
+            }
+        }
+        Err(HubError::NotFound(format!(
+            "No scene collection with name or id matching provided '{name}' was found"
+        ))
+        .into())
+    }
+
+    pub async fn activate_scene_collection(&self, collection_id: i32) -> anyhow::Result<()> {
+        let url = self.url(&format!(
+            "scenecollections?sceneCollectionId={collection_id}"
+        ));
+        let _res: serde_json::Value = get_request_with_json_response(url).await?;
+        Ok(())
+    }
+
+    pub async fn list_repeaters(&self) -> anyhow::Result<Vec<Repeater>> {
+        let mut resp: RepeatersResponse =
+            get_request_with_json_response(self.url("repeaters")).await?;
+        resp.repeater_data.sort_by_key(|item| item.id);
+        Ok(resp.repeater_data)
+    }
+
+    pub async fn list_automations(&self) -> anyhow::Result<Vec<Automation>> {
+        let mut resp: AutomationsResponse =
+            get_request_with_json_response(self.url("automations")).await?;
+        resp.automation_data
+            .sort_by_key(|item| (item.event.hour, item.event.minute, item.id));
+
+        Ok(resp.automation_data)
+    }
+
+    pub async fn list_scheduled_events(&self) -> anyhow::Result<Vec<ScheduledEvent>> {
+        let mut resp: ScheduledEventsResponse =
+            get_request_with_json_response(self.url("scheduledevents")).await?;
+        resp.scheduled_event_data.sort_by_key(|item| {
+            (
+                item.event.event_type,
+                item.event.hour,
+                item.event.minute,
+                item.id,
+            )
+        });
+
+        Ok(resp.scheduled_event_data)
+    }
+
+    pub async fn list_scenes_by_room(&self, room_id: i32) -> anyhow::Result<Vec<Scene>> {
         let mut resp: ScenesResponse =
-            get_request_with_json_response(self.url("api/scenes")).await?;
+            get_request_with_json_response(self.url(&format!("scenes?roomId={room_id}"))).await?;
+        // Not all hub firmware honors the roomId parameter,
+        // so filter here as well to be sure
+        resp.scene_data.retain(|scene| scene.room_id == room_id);
         resp.scene_data
             .sort_by_key(|item| (item.order, item.name.clone()));
 
@@ -42,31 +293,86 @@ impl Hub {
 
     pub async fn list_scene_members(&self) -> anyhow::Result<HashMap<i32, Vec<SceneMember>>> {
         let resp: SceneMembersResponse =
-            get_request_with_json_response(self.url("api/scenemembers")).await?;
+            get_request_with_json_response(self.url("scenemembers")).await?;
 
         let mut by_scene = HashMap::new();
         for member in resp.scene_member_data {
             by_scene
                 .entry(member.scene_id)
-                .or_insert_with(|| vec![])
+                .or_insert_with(Vec::new)
                 .push(member);
         }
 
         Ok(by_scene)
     }
 
-    pub async fn list_shades(
-        &self,
-        group_id: Option<i32>,
-        room_id: Option<i32>,
-    ) -> anyhow::Result<Vec<ShadeData>> {
+    /// Fetch the complete hub configuration, along with the hub's own
+    /// user data, and assemble it into a single json object that can
+    /// be saved as a backup of the hub configuration.
+    /// Names remain base64 encoded, just as the hub reports them.
+    /// Each collection is fetched in turn, rather than all at once,
+    /// to avoid hammering the hub on large installations.
+    pub async fn export_config(&self) -> anyhow::Result<serde_json::Value> {
+        let capabilities = self.get_capabilities().await?;
+        let user_data = self.get_user_data().await?;
+        let rooms = self.list_rooms().await?;
+        let shades = self.list_shades(None, None).await?;
+        let scenes = if capabilities.scenes {
+            self.list_scenes().await?
+        } else {
+            vec![]
+        };
+        let mut scene_members: Vec<SceneMember> = if capabilities.scenes {
+            self.list_scene_members()
+                .await?
+                .into_values()
+                .flatten()
+                .collect()
+        } else {
+            vec![]
+        };
+        scene_members.sort_by_key(|member| (member.scene_id, member.shade_id));
+        let scene_collections = if capabilities.scene_collections {
+            self.list_scene_collections().await?
+        } else {
+            vec![]
+        };
+        let scheduled_events = if capabilities.schedules {
+            self.list_scheduled_events().await?
+        } else {
+            vec![]
+        };
+
+        Ok(json!({
+            "schemaVersion": EXPORT_SCHEMA_VERSION,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "pviewVersion": crate::version_info::pview_version(),
+            "userData": user_data,
+            "rooms": rooms,
+            "shades": shades,
+            "scenes": scenes,
+            "sceneMembers": scene_members,
+            "sceneCollections": scene_collections,
+            "scheduledEvents": scheduled_events,
+        }))
+    }
+
+    fn list_shades_url(&self, group_id: Option<i32>, room_id: Option<i32>) -> HubRequest {
         let params = match (group_id, room_id) {
             (Some(g), Some(r)) => format!("?groupId={g}&roomId={r}"),
             (Some(g), None) => format!("?groupId={g}"),
             (None, Some(r)) => format!("?roomId={r}"),
             (None, None) => String::new(),
         };
-        let url = self.url(&format!("api/shades{params}"));
+        self.url(&format!("shades{params}"))
+    }
+
+    pub async fn list_shades(
+        &self,
+        group_id: Option<i32>,
+        room_id: Option<i32>,
+    ) -> anyhow::Result<Vec<ShadeData>> {
+        let url = self.list_shades_url(group_id, room_id);
 
         let mut resp: ShadesResponse = get_request_with_json_response(url).await?;
         resp.shade_data
@@ -75,8 +381,32 @@ impl Hub {
         Ok(resp.shade_data)
     }
 
-    pub fn with_addr(addr: IpAddr) -> Self {
-        Self { addr }
+    /// Returns the unmodified json response for `list_shades`
+    pub async fn list_shades_raw(
+        &self,
+        group_id: Option<i32>,
+        room_id: Option<i32>,
+    ) -> anyhow::Result<serde_json::Value> {
+        get_request_raw_json(self.list_shades_url(group_id, room_id)).await
+    }
+
+    pub fn with_addr(addr: IpAddr, generation: HubGeneration) -> Self {
+        Self {
+            addr,
+            generation,
+            headers: hub_headers(),
+            limiter: match REQUEST_CONCURRENCY.load(Ordering::Relaxed) {
+                0 => None,
+                limit => Some(Arc::new(Semaphore::new(limit))),
+            },
+        }
+    }
+
+    /// Create a Hub for the specified address, probing it to determine
+    /// which generation of the API it supports
+    pub async fn connect(addr: IpAddr) -> Self {
+        let generation = HubGeneration::detect(addr, &hub_headers()).await;
+        Self::with_addr(addr, generation)
     }
 
     pub async fn discover(timeout: Duration) -> anyhow::Result<Self> {
@@ -84,7 +414,7 @@ impl Hub {
             "Failed to discover the PowerView Hub. \
              Ensure that pview is running on the same network as the Hub!",
         )?;
-        Ok(Self::with_addr(addr))
+        Ok(Self::connect(addr).await)
     }
 
     pub async fn room_by_name(&self, name: &str) -> anyhow::Result<RoomData> {
@@ -97,87 +427,166 @@ impl Hub {
                 return Ok(room);
             }
         }
-        anyhow::bail!("No room with name or id matching provided '{name}' was found");
+        Err(HubError::NotFound(format!(
+            "No room with name or id matching provided '{name}' was found"
+        ))
+        .into())
     }
 
-    pub async fn change_battery_kind(
+    pub async fn create_room(
         &self,
-        shade_id: i32,
-        kind: ShadeBatteryKind,
-    ) -> anyhow::Result<ShadeData> {
-        let url = self.url(&format!("api/shades/{shade_id}"));
+        name: &str,
+        color_id: i32,
+        icon_id: i32,
+    ) -> anyhow::Result<RoomData> {
+        let url = self.url("rooms");
 
         #[derive(Deserialize, Debug)]
         struct Response {
-            shade: ShadeData,
+            room: RoomData,
         }
 
         let response: Response = request_with_json_response(
-            Method::PUT,
+            Method::POST,
             url,
             &json!({
-                "shade": {
-                    "batteryKind": kind
+                "room": {
+                    "name": Base64Name::new(name),
+                    "colorId": color_id,
+                    "iconId": icon_id,
                 }
             }),
         )
         .await?;
-        Ok(response.shade)
+        Ok(response.room)
     }
 
-    pub async fn change_shade_position(
-        &self,
-        shade_id: i32,
-        position: ShadePosition,
-    ) -> anyhow::Result<ShadeData> {
-        let url = self.url(&format!("api/shades/{shade_id}"));
+    pub async fn rename_room(&self, room_id: i32, name: &str) -> anyhow::Result<RoomData> {
+        let url = self.url(&format!("rooms/{room_id}"));
 
         #[derive(Deserialize, Debug)]
         struct Response {
-            shade: ShadeData,
+            room: RoomData,
         }
 
         let response: Response = request_with_json_response(
             Method::PUT,
             url,
             &json!({
-                "shade": {
-                    "positions": position
+                "room": {
+                    "name": Base64Name::new(name)
                 }
             }),
         )
         .await?;
-        Ok(response.shade)
+        Ok(response.room)
     }
 
-    pub async fn move_shade(
+    pub async fn delete_room(&self, room_id: i32) -> anyhow::Result<()> {
+        delete_request(self.url(&format!("rooms/{room_id}"))).await
+    }
+
+    /// Apply the changes in `shade`, a json object holding some
+    /// subset of the shade fields, and return the updated shade
+    async fn update_shade(
         &self,
         shade_id: i32,
-        motion: ShadeUpdateMotion,
+        shade: serde_json::Value,
     ) -> anyhow::Result<ShadeData> {
-        let url = self.url(&format!("api/shades/{shade_id}"));
+        let url = self.url(&format!("shades/{shade_id}"));
 
         #[derive(Deserialize, Debug)]
         struct Response {
             shade: ShadeData,
         }
 
-        let response: Response = request_with_json_response(
-            Method::PUT,
-            url,
-            &json!({
-                "shade": {
-                    "motion": motion
-                }
-            }),
-        )
-        .await?;
+        let response: Response =
+            request_with_json_response(Method::PUT, url, &json!({ "shade": shade })).await?;
         Ok(response.shade)
     }
 
+    pub async fn change_battery_kind(
+        &self,
+        shade_id: i32,
+        kind: ShadeBatteryKind,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "batteryKind": kind }))
+            .await
+    }
+
+    pub async fn rename_shade(&self, shade_id: i32, name: &str) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "name": Base64Name::new(name) }))
+            .await
+    }
+
+    /// Change the secondary name of a shade, which is used by some
+    /// integrations to label the middle rail or blackout blind
+    pub async fn rename_shade_secondary(
+        &self,
+        shade_id: i32,
+        name: &str,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "secondaryName": Base64Name::new(name) }))
+            .await
+    }
+
+    pub async fn change_shade_room(
+        &self,
+        shade_id: i32,
+        room_id: i32,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "roomId": room_id }))
+            .await
+    }
+
+    pub async fn change_shade_position(
+        &self,
+        shade_id: i32,
+        position: ShadePosition,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "positions": position }))
+            .await
+    }
+
+    /// Change the display order of a shade
+    pub async fn change_shade_order(
+        &self,
+        shade_id: i32,
+        new_order: i32,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "order": new_order }))
+            .await
+    }
+
+    /// Move the shade to its favorite position, as configured
+    /// in the PowerView app
+    pub async fn move_shade_to_favorite(&self, shade_id: i32) -> anyhow::Result<ShadeData> {
+        self.move_shade(shade_id, ShadeUpdateMotion::Heart).await
+    }
+
+    /// Program the position that the shade moves to when it is
+    /// sent the Heart motion
+    pub async fn set_shade_favorite(
+        &self,
+        shade_id: i32,
+        positions: ShadePosition,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "favorite": positions }))
+            .await
+    }
+
+    pub async fn move_shade(
+        &self,
+        shade_id: i32,
+        motion: ShadeUpdateMotion,
+    ) -> anyhow::Result<ShadeData> {
+        self.update_shade(shade_id, json!({ "motion": motion }))
+            .await
+    }
+
     /// Returns the list of affected shades
     pub async fn activate_scene(&self, scene_id: i32) -> anyhow::Result<Vec<i32>> {
-        let url = self.url(&format!("api/scenes?sceneId={scene_id}"));
+        let url = self.url(&format!("scenes?sceneId={scene_id}"));
 
         #[derive(Deserialize, Debug)]
         #[serde(rename_all = "camelCase")]
@@ -189,18 +598,137 @@ impl Hub {
         Ok(response.shade_ids)
     }
 
+    /// Activate several scenes at the same time.
+    /// Returns the scene id and the ids of the shades that it moved
+    /// for each scene, in the same order as `scene_ids`.
+    pub async fn activate_scenes_parallel(
+        &self,
+        scene_ids: &[i32],
+    ) -> anyhow::Result<Vec<(i32, Vec<i32>)>> {
+        futures::future::try_join_all(scene_ids.iter().map(|&scene_id| async move {
+            let shade_ids = self
+                .activate_scene(scene_id)
+                .await
+                .with_context(|| format!("activating scene {scene_id}"))?;
+            anyhow::Ok((scene_id, shade_ids))
+        }))
+        .await
+    }
+
+    pub async fn get_scene_by_id(&self, scene_id: i32) -> anyhow::Result<Scene> {
+        let url = self.url(&format!("scenes/{scene_id}"));
+
+        #[derive(Deserialize, Debug)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            scene: Scene,
+        }
+        let response: Response = get_request_with_json_response(url).await?;
+
+        Ok(response.scene)
+    }
+
     pub async fn scene_by_name(&self, name: &str) -> anyhow::Result<Scene> {
         let scenes = self.list_scenes().await?;
         for s in scenes {
             if s.name.eq_ignore_ascii_case(name) {
                 return Ok(s);
             }
+            if s.id.to_string() == name {
+                return Ok(s);
+            }
+        }
+        Err(HubError::NotFound(format!(
+            "No scene with name or id matching provided '{name}' was found"
+        ))
+        .into())
+    }
+
+    /// Change the display order of a scene
+    pub async fn change_scene_order(&self, scene_id: i32, new_order: i32) -> anyhow::Result<Scene> {
+        let url = self.url(&format!("scenes/{scene_id}"));
+
+        #[derive(Deserialize, Debug)]
+        struct Response {
+            scene: Scene,
         }
-        anyhow::bail!("No scene with name matching '{name}' was found");
+
+        let response: Response = request_with_json_response(
+            Method::PUT,
+            url,
+            &json!({
+                "scene": {
+                    "order": new_order
+                }
+            }),
+        )
+        .await?;
+        Ok(response.scene)
+    }
+
+    pub async fn create_scene(&self, name: &str, room_id: i32) -> anyhow::Result<Scene> {
+        let url = self.url("scenes");
+
+        #[derive(Deserialize, Debug)]
+        struct Response {
+            scene: Scene,
+        }
+
+        let response: Response = request_with_json_response(
+            Method::POST,
+            url,
+            &json!({
+                "scene": {
+                    "name": Base64Name::new(name),
+                    "roomId": room_id,
+                    "colorId": 0,
+                    "iconId": 0,
+                }
+            }),
+        )
+        .await?;
+        Ok(response.scene)
+    }
+
+    pub async fn delete_scene(&self, scene_id: i32) -> anyhow::Result<()> {
+        delete_request(self.url(&format!("scenes/{scene_id}"))).await
+    }
+
+    pub async fn add_scene_member(
+        &self,
+        scene_id: i32,
+        shade_id: i32,
+        position: ShadePosition,
+    ) -> anyhow::Result<SceneMember> {
+        let url = self.url("scenemembers");
+
+        #[derive(Deserialize, Debug)]
+        #[serde(rename_all = "camelCase")]
+        struct Response {
+            scene_member: SceneMember,
+        }
+
+        let response: Response = request_with_json_response(
+            Method::POST,
+            url,
+            &json!({
+                "sceneMember": {
+                    "sceneId": scene_id,
+                    "shadeId": shade_id,
+                    "positions": position
+                }
+            }),
+        )
+        .await?;
+        Ok(response.scene_member)
+    }
+
+    pub async fn delete_scene_member(&self, member_id: i32) -> anyhow::Result<()> {
+        delete_request(self.url(&format!("scenemembers/{member_id}"))).await
     }
 
     pub async fn shade_update_battery_level(&self, shade_id: i32) -> anyhow::Result<ShadeData> {
-        let url = self.url(&format!("api/shades/{shade_id}?updateBatteryLevel=true"));
+        let url = self.url(&format!("shades/{shade_id}?updateBatteryLevel=true"));
 
         #[derive(Deserialize, Debug)]
         #[serde(rename_all = "camelCase")]
@@ -216,8 +744,40 @@ impl Hub {
         Ok(response.shade)
     }
 
+    /// Ask the hub to re-measure the battery level of every shade
+    /// in a single request. Not all hubs support this; for those that
+    /// don't, each shade is asked in turn via `shade_update_battery_level`.
+    pub async fn get_all_shades_battery_status(&self) -> anyhow::Result<Vec<ShadeData>> {
+        let url = self.url("shades?updateBatteryLevel=true");
+        match get_request_with_json_response::<_, ShadesResponse>(url).await {
+            Ok(mut resp) => {
+                resp.shade_data
+                    .sort_by_key(|item| (item.order, item.name.clone()));
+                Ok(resp.shade_data)
+            }
+            Err(err)
+                if matches!(
+                    err.downcast_ref(),
+                    Some(HubError::NotFound(_) | HubError::BadStatus { .. })
+                ) =>
+            {
+                log::warn!("bulk battery refresh is not available: {err:#}");
+                let mut shades = vec![];
+                for shade in self.list_shades(None, None).await? {
+                    let refreshed = self
+                        .shade_update_battery_level(shade.id)
+                        .await
+                        .with_context(|| format!("shade {} {}", shade.id, shade.name()))?;
+                    shades.push(refreshed);
+                }
+                Ok(shades)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     pub async fn shade_refresh_position(&self, shade_id: i32) -> anyhow::Result<ShadeData> {
-        let url = self.url(&format!("api/shades/{shade_id}?refresh=true"));
+        let url = self.url(&format!("shades/{shade_id}?refresh=true"));
 
         #[derive(Deserialize, Debug)]
         #[serde(rename_all = "camelCase")]
@@ -234,7 +794,7 @@ impl Hub {
     }
 
     pub async fn shade_by_id(&self, shade_id: i32) -> anyhow::Result<ShadeData> {
-        let url = self.url(&format!("api/shades/{shade_id}"));
+        let url = self.url(&format!("shades/{shade_id}"));
 
         #[derive(Deserialize, Debug)]
         #[serde(rename_all = "camelCase")]
@@ -246,6 +806,11 @@ impl Hub {
         Ok(response.shade)
     }
 
+    /// Returns the unmodified json response for `shade_by_id`
+    pub async fn shade_by_id_raw(&self, shade_id: i32) -> anyhow::Result<serde_json::Value> {
+        get_request_raw_json(self.url(&format!("shades/{shade_id}"))).await
+    }
+
     pub async fn shade_by_name(&self, name: &str) -> anyhow::Result<ResolvedShadeData> {
         let shades = self.list_shades(None, None).await?;
         for shade in shades {
@@ -259,17 +824,114 @@ impl Hub {
                 return Ok(ResolvedShadeData::Primary(shade));
             }
         }
-        anyhow::bail!(
+        Err(HubError::NotFound(format!(
             "No shade with name, secondary name or id matching provided '{name}' was found"
-        );
+        ))
+        .into())
+    }
+
+    /// Returns the shades whose name or secondary name matches the
+    /// glob `pattern`, compared ignoring case.
+    /// A shade whose name matches resolves to its primary rail,
+    /// otherwise to its secondary rail.
+    /// It is an error for the pattern to match no shades.
+    pub async fn shades_matching(&self, pattern: &str) -> anyhow::Result<Vec<ResolvedShadeData>> {
+        let glob = glob::Pattern::new(pattern)
+            .with_context(|| format!("invalid shade filter '{pattern}'"))?;
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+
+        let shades: Vec<ResolvedShadeData> = self
+            .list_shades(None, None)
+            .await?
+            .into_iter()
+            .filter_map(|shade| {
+                if glob.matches_with(shade.name(), options) {
+                    Some(ResolvedShadeData::Primary(shade))
+                } else if glob.matches_with(&shade.secondary_name(), options) {
+                    Some(ResolvedShadeData::Secondary(shade))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        if shades.is_empty() {
+            return Err(HubError::NotFound(format!(
+                "No shade with name or secondary name matching '{pattern}' was found"
+            ))
+            .into());
+        }
+        Ok(shades)
+    }
+
+    /// Resolve a user supplied shade id or name.
+    /// Numeric values are first tried as an id, falling back to
+    /// matching by name.
+    pub async fn shade_by_id_or_name(&self, name: &str) -> anyhow::Result<ResolvedShadeData> {
+        if let Ok(shade_id) = name.parse::<i32>() {
+            match self.shade_by_id(shade_id).await {
+                Ok(shade) => return Ok(ResolvedShadeData::Primary(shade)),
+                // There is no such id, but there may be a shade
+                // with a numeric name
+                Err(err) if matches!(err.downcast_ref(), Some(HubError::NotFound(_))) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.shade_by_name(name).await
+    }
+
+    /// Returns the set of api features supported by the hub.
+    /// Only Gen3 hubs are able to report this; Gen2 hubs, and Gen3 hubs
+    /// whose firmware predates the capabilities api, are assumed to
+    /// support everything in the Gen2 api.
+    pub async fn get_capabilities(&self) -> anyhow::Result<HubCapabilities> {
+        if self.generation == HubGeneration::Gen2 {
+            return Ok(HubCapabilities::gen2());
+        }
+        match get_request_with_json_response(self.url("capabilities")).await {
+            Ok(caps) => Ok(caps),
+            Err(err) if matches!(err.downcast_ref(), Some(HubError::NotFound(_))) => {
+                Ok(HubCapabilities::gen2())
+            }
+            Err(err) => Err(err),
+        }
     }
 
     pub async fn get_user_data(&self) -> anyhow::Result<UserData> {
-        let resp: UserDataResponse =
-            get_request_with_json_response(self.url("api/userdata")).await?;
+        let resp: UserDataResponse = get_request_with_json_response(self.url("userdata")).await?;
         Ok(resp.user_data)
     }
 
+    /// Changes the color and brightness of the hub LED
+    pub async fn set_led_color(&self, color: Color) -> anyhow::Result<UserData> {
+        let resp: UserDataResponse = request_with_json_response(
+            Method::PUT,
+            self.url("userdata"),
+            &json!({
+                "userData": {
+                    "color": color
+                }
+            }),
+        )
+        .await?;
+        Ok(resp.user_data)
+    }
+
+    /// Ask the hub to restart. The hub stops responding for a while
+    /// afterwards; use `get_user_data` to find out when it is back.
+    /// Gen3 hubs map this to `gateway/reboot`.
+    pub async fn reboot(&self) -> anyhow::Result<()> {
+        post_request(self.url("userdata/reboot")).await
+    }
+
+    /// Returns the unmodified json response for `get_user_data`
+    pub async fn get_user_data_raw(&self) -> anyhow::Result<serde_json::Value> {
+        get_request_raw_json(self.url("userdata")).await
+    }
+
     /// Figure out the local address that is determined
     /// by the kernel for communication with the hub
     pub async fn suggest_bind_address(&self) -> anyhow::Result<IpAddr> {
@@ -277,8 +939,57 @@ impl Hub {
         Ok(stream.local_addr()?.ip())
     }
 
+    pub async fn get_home_automation_hook(&self) -> anyhow::Result<HomeAutomationHook> {
+        let resp: HomeAutomationHookResponse =
+            get_request_with_json_response(self.url("homeautomation")).await?;
+        Ok(resp.homeautomation)
+    }
+
+    /// Re-apply a hook configuration previously obtained
+    /// via `get_home_automation_hook`.
+    pub async fn restore_home_automation_hook(
+        &self,
+        hook: &HomeAutomationHook,
+    ) -> anyhow::Result<()> {
+        match &hook.post_back_url {
+            Some(url) if hook.enabled => self.enable_home_automation_hook(url).await,
+            _ => self.disable_home_automation_hook().await,
+        }
+    }
+
+    /// Verify that the configured home automation postback url is
+    /// reachable by sending it an empty batch of events, in the same
+    /// encoding that the hub uses.
+    /// The hub has no endpoint to trigger a postback on demand, so
+    /// this is sent from this host rather than from the hub itself.
+    /// Returns the url that was tested, along with the outcome of
+    /// the test.
+    pub async fn test_home_automation_hook(&self) -> anyhow::Result<(String, anyhow::Result<()>)> {
+        let hook = self.get_home_automation_hook().await?;
+        let url = match &hook.post_back_url {
+            Some(url) if hook.enabled => url,
+            _ => anyhow::bail!(
+                "The home automation hook is not enabled on hub at {}",
+                self.addr
+            ),
+        };
+
+        // The hub stores the url without a scheme
+        let url = if url.contains("://") {
+            url.to_string()
+        } else {
+            format!("http://{url}")
+        };
+
+        // base64 encoded empty json array
+        let outcome = post_text_request(&url, "W10=".to_string())
+            .await
+            .with_context(|| format!("testing postback url {url}"));
+        Ok((url, outcome))
+    }
+
     pub async fn enable_home_automation_hook(&self, postback_url: &str) -> anyhow::Result<()> {
-        let url = self.url("api/homeautomation");
+        let url = self.url("homeautomation");
 
         let _res: serde_json::Value = request_with_json_response(
             Method::PUT,
@@ -293,6 +1004,22 @@ impl Hub {
         .await?;
         Ok(())
     }
+
+    pub async fn disable_home_automation_hook(&self) -> anyhow::Result<()> {
+        let url = self.url("homeautomation");
+
+        let _res: serde_json::Value = request_with_json_response(
+            Method::PUT,
+            url,
+            &json!({
+                "homeautomation": {
+                    "enabled": false
+                }
+            }),
+        )
+        .await?;
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -305,6 +1032,12 @@ impl ResolvedShadeData {
     pub fn is_primary(&self) -> bool {
         matches!(self, Self::Primary(_))
     }
+
+    pub fn into_inner(self) -> ShadeData {
+        match self {
+            Self::Primary(a) | Self::Secondary(a) => a,
+        }
+    }
 }
 
 impl std::ops::Deref for ResolvedShadeData {
@@ -315,3 +1048,20 @@ impl std::ops::Deref for ResolvedShadeData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hub_url_formatting() {
+        assert_eq!(
+            hub_url("192.168.1.5".parse().unwrap(), "api/shades"),
+            "http://192.168.1.5/api/shades"
+        );
+        assert_eq!(
+            hub_url("fe80::1".parse().unwrap(), "api/shades"),
+            "http://[fe80::1]/api/shades"
+        );
+    }
+}
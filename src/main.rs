@@ -10,6 +10,10 @@ mod discovery;
 mod hass_helper;
 mod http_helpers;
 mod hub;
+mod metrics;
+mod mqtt_helper;
+mod postback;
+mod schedule;
 mod version_info;
 
 use crate::hub::*;
@@ -32,18 +36,69 @@ pub struct Args {
     #[arg(long)]
     hub_serial: Option<String>,
 
+    /// A token to send to the hub as a credential with every request.
+    /// Current Gen2 hubs don't require this, but some newer hub
+    /// firmware may.
+    /// You may also set this via the PV_HUB_TOKEN environment variable.
+    #[arg(long)]
+    hub_token: Option<String>,
+
     #[arg(skip)]
     hub_instance: Mutex<Option<Hub>>,
 
     #[arg(long, default_value = "15", value_parser = parse_duration)]
     discovery_timeout: Duration,
+
+    /// How many times to retry discovery after the first attempt
+    /// fails. The first attempt waits up to --discovery-timeout seconds,
+    /// and each retry waits twice as long as the attempt before it.
+    #[arg(long, default_value = "0")]
+    discovery_retries: usize,
+
+    /// How many seconds to wait between discovery attempts
+    #[arg(long, default_value = "2", value_parser = parse_duration)]
+    discovery_retry_delay: Duration,
+
+    /// Only accept hubs discovered via the specified network interface.
+    /// This may be either an interface name or an address assigned
+    /// to that interface, and is useful on machines that are attached
+    /// to multiple networks.  Use `ip addr` on Linux, `ifconfig` on
+    /// macOS or `ipconfig` on Windows to find the interface name.
+    /// You may also set this via the PV_DISCOVERY_INTERFACE environment
+    /// variable.
+    #[arg(long)]
+    discovery_interface: Option<String>,
+
+    /// How many seconds to wait for an individual request to the hub
+    /// to complete. This is distinct from --discovery-timeout, which
+    /// limits how long we wait to locate the hub on the network.
+    /// The default is 60 seconds.
+    /// You may also set this via the PV_REQUEST_TIMEOUT environment variable.
+    #[arg(long, value_parser = parse_duration)]
+    request_timeout: Option<Duration>,
+
+    /// Ignore fields in the hub responses that pview doesn't know
+    /// about, logging them as warnings, rather than failing to parse
+    /// the response. This can help to keep things working after the
+    /// hub firmware is updated.
+    #[arg(long)]
+    lenient: bool,
 }
 
-fn parse_duration(arg: &str) -> Result<Duration, std::num::ParseIntError> {
+pub fn parse_duration(arg: &str) -> Result<Duration, std::num::ParseIntError> {
     let seconds = arg.parse()?;
     Ok(Duration::from_secs(seconds))
 }
 
+/// Like parse_duration, but rejects 0
+pub fn parse_nonzero_duration(arg: &str) -> Result<Duration, String> {
+    match parse_duration(arg) {
+        Ok(duration) if duration.is_zero() => Err("must be at least 1 second".to_string()),
+        Ok(duration) => Ok(duration),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
 #[derive(Parser, Debug)]
 pub enum SubCommand {
     ListScenes(commands::list_scenes::ListScenesCommand),
@@ -51,9 +106,47 @@ pub enum SubCommand {
     InspectShade(commands::inspect_shade::InspectShadeCommand),
     MoveShade(commands::move_shade::MoveShadeCommand),
     ActivateScene(commands::activate_scene::ActivateSceneCommand),
-    ServeMqtt(commands::serve_mqtt::ServeMqttCommand),
+    ServeMqtt(Box<commands::serve_mqtt::ServeMqttCommand>),
     HubInfo(commands::hub_info::HubInfoCommand),
     ListHubs(commands::list_hubs::ListHubsCommand),
+    CreateScene(commands::create_scene::CreateSceneCommand),
+    DeleteScene(commands::delete_scene::DeleteSceneCommand),
+    #[command(alias = "scene-add-shade")]
+    AddShadeToScene(commands::add_shade_to_scene::AddShadeToSceneCommand),
+    CreateRoom(commands::create_room::CreateRoomCommand),
+    MoveRoom(commands::move_room::MoveRoomCommand),
+    #[command(alias = "watch")]
+    WatchShades(commands::watch_shades::WatchShadesCommand),
+    ListRooms(commands::list_rooms::ListRoomsCommand),
+    DeleteRoom(commands::delete_room::DeleteRoomCommand),
+    RenameShade(commands::rename_shade::RenameShadeCommand),
+    RenameRoom(commands::rename_room::RenameRoomCommand),
+    TestAutomationHook(commands::test_automation_hook::TestAutomationHookCommand),
+    #[command(alias = "scene-remove-shade")]
+    RemoveShadeFromScene(commands::remove_shade_from_scene::RemoveShadeFromSceneCommand),
+    StopAll(commands::stop_all::StopAllCommand),
+    ActivateScenes(commands::activate_scenes::ActivateScenesCommand),
+    Firmware(commands::firmware::FirmwareCommand),
+    ListSceneCollections(commands::list_scene_collections::ListSceneCollectionsCommand),
+    ActivateSceneCollection(commands::activate_scene_collection::ActivateSceneCollectionCommand),
+    RefreshBattery(commands::refresh_battery::RefreshBatteryCommand),
+    #[command(alias = "get-hook")]
+    ShowAutomationHook(commands::show_automation_hook::ShowAutomationHookCommand),
+    RefreshPosition(commands::refresh_position::RefreshPositionCommand),
+    SetPowerSource(commands::set_power_source::SetPowerSourceCommand),
+    Status(commands::status::StatusCommand),
+    CalibrateShade(commands::calibrate_shade::CalibrateShadeCommand),
+    ListAutomations(commands::list_automations::ListAutomationsCommand),
+    SetFavorite(commands::set_favorite::SetFavoriteCommand),
+    ExportConfig(commands::export_config::ExportConfigCommand),
+    ListRepeaters(commands::list_repeaters::ListRepeatersCommand),
+    AllShades(commands::all_shades::AllShadesCommand),
+    SetHubLed(commands::set_hub_led::SetHubLedCommand),
+    RebootHub(commands::reboot_hub::RebootHubCommand),
+    ListSchedules(commands::list_schedules::ListSchedulesCommand),
+    ReorderScene(commands::reorder_scene::ReorderSceneCommand),
+    ReorderShade(commands::reorder_shade::ReorderShadeCommand),
+    Backup(commands::backup::BackupCommand),
 }
 
 impl SubCommand {
@@ -67,6 +160,40 @@ impl SubCommand {
             Self::ServeMqtt(cmd) => cmd.run(args).await,
             Self::HubInfo(cmd) => cmd.run(args).await,
             Self::ListHubs(cmd) => cmd.run(args).await,
+            Self::CreateScene(cmd) => cmd.run(args).await,
+            Self::DeleteScene(cmd) => cmd.run(args).await,
+            Self::AddShadeToScene(cmd) => cmd.run(args).await,
+            Self::CreateRoom(cmd) => cmd.run(args).await,
+            Self::MoveRoom(cmd) => cmd.run(args).await,
+            Self::WatchShades(cmd) => cmd.run(args).await,
+            Self::ListRooms(cmd) => cmd.run(args).await,
+            Self::DeleteRoom(cmd) => cmd.run(args).await,
+            Self::RenameShade(cmd) => cmd.run(args).await,
+            Self::RenameRoom(cmd) => cmd.run(args).await,
+            Self::TestAutomationHook(cmd) => cmd.run(args).await,
+            Self::RemoveShadeFromScene(cmd) => cmd.run(args).await,
+            Self::StopAll(cmd) => cmd.run(args).await,
+            Self::ActivateScenes(cmd) => cmd.run(args).await,
+            Self::Firmware(cmd) => cmd.run(args).await,
+            Self::ListSceneCollections(cmd) => cmd.run(args).await,
+            Self::ActivateSceneCollection(cmd) => cmd.run(args).await,
+            Self::RefreshBattery(cmd) => cmd.run(args).await,
+            Self::ShowAutomationHook(cmd) => cmd.run(args).await,
+            Self::RefreshPosition(cmd) => cmd.run(args).await,
+            Self::SetPowerSource(cmd) => cmd.run(args).await,
+            Self::Status(cmd) => cmd.run(args).await,
+            Self::CalibrateShade(cmd) => cmd.run(args).await,
+            Self::ListAutomations(cmd) => cmd.run(args).await,
+            Self::SetFavorite(cmd) => cmd.run(args).await,
+            Self::ExportConfig(cmd) => cmd.run(args).await,
+            Self::ListRepeaters(cmd) => cmd.run(args).await,
+            Self::AllShades(cmd) => cmd.run(args).await,
+            Self::SetHubLed(cmd) => cmd.run(args).await,
+            Self::RebootHub(cmd) => cmd.run(args).await,
+            Self::ListSchedules(cmd) => cmd.run(args).await,
+            Self::ReorderScene(cmd) => cmd.run(args).await,
+            Self::ReorderShade(cmd) => cmd.run(args).await,
+            Self::Backup(cmd) => cmd.run(args).await,
         }
     }
 }
@@ -81,12 +208,35 @@ impl Args {
     }
 
     pub fn hub_ip(&self) -> anyhow::Result<Option<IpAddr>> {
-        match self.hub_ip.clone() {
+        match self.hub_ip {
             Some(u) => Ok(Some(u)),
             None => opt_env_var("PV_HUB_IP"),
         }
     }
 
+    pub fn request_timeout(&self) -> anyhow::Result<Duration> {
+        match self.request_timeout {
+            Some(t) => Ok(t),
+            None => Ok(opt_env_var::<u64>("PV_REQUEST_TIMEOUT")?
+                .map(Duration::from_secs)
+                .unwrap_or(crate::http_helpers::DEFAULT_REQUEST_TIMEOUT)),
+        }
+    }
+
+    pub fn discovery_interface(&self) -> anyhow::Result<Option<String>> {
+        match self.discovery_interface.clone() {
+            Some(iface) => Ok(Some(iface)),
+            None => opt_env_var("PV_DISCOVERY_INTERFACE"),
+        }
+    }
+
+    pub fn hub_token(&self) -> anyhow::Result<Option<String>> {
+        match self.hub_token.clone() {
+            Some(token) => Ok(Some(token)),
+            None => opt_env_var("PV_HUB_TOKEN"),
+        }
+    }
+
     pub fn hub_serial(&self) -> anyhow::Result<Option<String>> {
         match self.hub_serial.clone() {
             Some(u) => Ok(Some(u)),
@@ -102,19 +252,27 @@ impl Args {
                 let addr = self.hub_ip()?;
 
                 let hub = match addr {
-                    Some(addr) => Hub::with_addr(addr),
+                    Some(addr) => Hub::connect(addr).await,
                     None => {
-                        let serial = self.hub_serial()?;
-                        match serial {
-                            Some(serial) => {
-                                crate::discovery::resolve_hub_with_serial(
-                                    Some(self.discovery_timeout),
-                                    &serial,
-                                )
-                                .await?
-                            }
-                            None => Hub::discover(self.discovery_timeout).await?,
-                        }
+                        let serial = &self.hub_serial()?;
+                        crate::discovery::retry_discovery(
+                            self.discovery_retries,
+                            self.discovery_retry_delay,
+                            self.discovery_timeout,
+                            |timeout| async move {
+                                match serial.as_deref() {
+                                    Some(serial) => {
+                                        crate::discovery::resolve_hub_with_serial(
+                                            Some(timeout),
+                                            serial,
+                                        )
+                                        .await
+                                    }
+                                    None => Hub::discover(timeout).await,
+                                }
+                            },
+                        )
+                        .await?
                     }
                 };
                 lock.replace(hub.clone());
@@ -139,15 +297,40 @@ where
     }
 }
 
-fn setup_logger() {
-    fn resolve_timezone() -> chrono_tz::Tz {
-        std::env::var("TZ")
-            .or_else(|_| iana_time_zone::get_timezone())
-            .ok()
-            .and_then(|name| name.parse().ok())
-            .unwrap_or(chrono_tz::UTC)
+pub fn resolve_timezone() -> chrono_tz::Tz {
+    std::env::var("TZ")
+        .or_else(|_| iana_time_zone::get_timezone())
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(chrono_tz::UTC)
+}
+
+/// Resolves when the process is asked to terminate, either
+/// via CTRL-C or SIGTERM
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+                return;
+            }
+            Err(err) => {
+                log::error!("Unable to listen for SIGTERM: {err:#}");
+            }
+        }
+    }
+    if let Err(err) = tokio::signal::ctrl_c().await {
+        log::error!("Unable to listen for CTRL-C: {err:#}");
+        std::future::pending::<()>().await;
     }
+}
 
+fn setup_logger() {
     let tz = resolve_timezone();
     let utc_suffix = if tz == chrono_tz::UTC { "Z" } else { "" };
 
@@ -183,7 +366,8 @@ fn setup_logger() {
 }
 
 // We use only 2 worker threads here because, by design and intent,
-// we largely serialize request processing to avoid swamping the hub.
+// we largely serialize request processing to avoid swamping the hub;
+// see serve-mqtt --hub-concurrency.
 // On machines with a high core count, using the default setting would
 // spawn a lot of threads that we will never use. Keeping the thread
 // count small helps to reduce the resource footprint when running
@@ -198,5 +382,13 @@ async fn main() -> anyhow::Result<()> {
     setup_logger();
 
     let args = Args::parse();
+    http_helpers::set_request_timeout(args.request_timeout()?)?;
+    http_helpers::set_lenient(args.lenient);
+    if let Some(token) = args.hub_token()? {
+        hub::set_hub_token(&token)?;
+    }
+    if let Some(iface) = args.discovery_interface()? {
+        discovery::set_discovery_interface(&iface)?;
+    }
     args.run().await
 }
@@ -0,0 +1,140 @@
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Counters and gauges describing the health of the mqtt bridge,
+/// exposed in the Prometheus text format
+#[derive(Default)]
+pub struct Metrics {
+    postbacks_received: AtomicU64,
+    mqtt_messages_dispatched: AtomicU64,
+    hub_responding: AtomicBool,
+    /// The most recently reported position, keyed by shade id
+    shade_positions: Mutex<BTreeMap<String, u8>>,
+    /// The most recently reported battery level, keyed by shade id
+    battery_percentages: Mutex<BTreeMap<String, u8>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            hub_responding: AtomicBool::new(true),
+            ..Default::default()
+        }
+    }
+
+    pub fn postback_received(&self) {
+        self.postbacks_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn mqtt_message_dispatched(&self) {
+        self.mqtt_messages_dispatched
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_hub_responding(&self, responding: bool) {
+        self.hub_responding.store(responding, Ordering::Relaxed);
+    }
+
+    pub fn set_shade_position(&self, shade_id: &str, position: u8) {
+        self.shade_positions
+            .lock()
+            .unwrap()
+            .insert(shade_id.to_string(), position);
+    }
+
+    pub fn set_battery_percent(&self, shade_id: &str, percent: u8) {
+        self.battery_percentages
+            .lock()
+            .unwrap()
+            .insert(shade_id.to_string(), percent);
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, u64)>| {
+            let _ = writeln!(out, "# HELP pv2mqtt_{name} {help}");
+            let _ = writeln!(out, "# TYPE pv2mqtt_{name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "pv2mqtt_{name}{labels} {value}");
+            }
+        };
+
+        let by_shade = |map: &Mutex<BTreeMap<String, u8>>| {
+            map.lock()
+                .unwrap()
+                .iter()
+                .map(|(shade_id, value)| (format!("{{shade_id=\"{shade_id}\"}}"), *value as u64))
+                .collect()
+        };
+
+        metric(
+            "postbacks_received_total",
+            "counter",
+            "Number of postbacks received from the hub",
+            vec![(
+                String::new(),
+                self.postbacks_received.load(Ordering::Relaxed),
+            )],
+        );
+        metric(
+            "mqtt_messages_dispatched_total",
+            "counter",
+            "Number of mqtt messages dispatched to handlers",
+            vec![(
+                String::new(),
+                self.mqtt_messages_dispatched.load(Ordering::Relaxed),
+            )],
+        );
+        metric(
+            "hub_request_failures_total",
+            "counter",
+            "Number of requests to the hub that failed",
+            vec![(String::new(), crate::http_helpers::request_failure_count())],
+        );
+        metric(
+            "hub_responding",
+            "gauge",
+            "Whether the hub is currently responding",
+            vec![(
+                String::new(),
+                self.hub_responding.load(Ordering::Relaxed) as u64,
+            )],
+        );
+        metric(
+            "shade_position_percent",
+            "gauge",
+            "The most recently reported position of each shade",
+            by_shade(&self.shade_positions),
+        );
+        metric(
+            "shade_battery_percent",
+            "gauge",
+            "The most recently reported battery level of each shade",
+            by_shade(&self.battery_percentages),
+        );
+
+        out
+    }
+}
+
+async fn metrics_handler(State(metrics): State<Arc<Metrics>>) -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render(),
+    )
+}
+
+/// Returns a router that serves `metrics` at `/metrics`
+pub fn metrics_router(metrics: Arc<Metrics>) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .with_state(metrics)
+}
@@ -0,0 +1,111 @@
+use mosquitto_rs::router::{FromRequest, Payload, Request, RouterError, RouterResult};
+use serde::de::DeserializeOwned;
+
+/// An extractor for message payloads that are json encoded.
+/// This is the json counterpart of `Payload`, which can only parse
+/// types that implement `FromStr`:
+///
+/// ```ignore
+/// async fn my_handler(JsonPayload(cmd): JsonPayload<MyCommand>) -> anyhow::Result<()> {
+///     Ok(())
+/// }
+/// ```
+// No route currently accepts structured payloads, but this is here
+// for those that will
+#[allow(dead_code)]
+pub struct JsonPayload<T>(pub T);
+
+impl<S, T> FromRequest<S> for JsonPayload<T>
+where
+    T: DeserializeOwned,
+{
+    fn from_request(request: &Request<S>) -> RouterResult<Self> {
+        let Payload(text): Payload<String> = Payload::from_request(request)?;
+        let result: T =
+            serde_json::from_str(&text).map_err(|err| RouterError::PayloadParseFailed {
+                text: text.clone(),
+                error: format!("invalid json: {err}"),
+            })?;
+        Ok(Self(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mosquitto_rs::router::Dispatcher;
+    use mosquitto_rs::Message;
+    use serde::Deserialize;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Command {
+        percent: u8,
+    }
+
+    /// Dispatch a message holding `payload` to a handler that
+    /// extracts a `JsonPayload<Command>`
+    async fn extract(payload: &str) -> anyhow::Result<Command> {
+        let extracted = Arc::new(Mutex::new(None));
+        let dispatcher: Dispatcher<()> = Dispatcher::new(Box::new({
+            let extracted = extracted.clone();
+            move |request| {
+                let result = JsonPayload::<Command>::from_request(&request);
+                let extracted = extracted.clone();
+                Box::pin(async move {
+                    let JsonPayload(cmd) = result?;
+                    extracted.lock().unwrap().replace(cmd);
+                    Ok(())
+                })
+            }
+        }));
+
+        let message = Message {
+            topic: "test".to_string(),
+            payload: payload.as_bytes().to_vec(),
+            ..Default::default()
+        };
+        dispatcher
+            .call(serde_json::Value::Null, message, ())
+            .await?;
+        let cmd = extracted.lock().unwrap().take().unwrap();
+        Ok(cmd)
+    }
+
+    #[tokio::test]
+    async fn valid_json() {
+        assert_eq!(
+            extract(r#"{"percent": 42}"#).await.unwrap(),
+            Command { percent: 42 }
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_json() {
+        let err = extract("{percent: 42}").await.unwrap_err();
+        match err.downcast_ref::<RouterError>() {
+            Some(RouterError::PayloadParseFailed { text, error }) => {
+                assert_eq!(text, "{percent: 42}");
+                assert!(
+                    error.starts_with("invalid json: key must be a string"),
+                    "{error}"
+                );
+            }
+            _ => panic!("unexpected error {err:#}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn wrong_shape() {
+        let err = extract(r#"{"percent": "high"}"#).await.unwrap_err();
+        match err.downcast_ref::<RouterError>() {
+            Some(RouterError::PayloadParseFailed { error, .. }) => {
+                assert!(
+                    error.starts_with("invalid json: invalid type: string"),
+                    "{error}"
+                );
+            }
+            _ => panic!("unexpected error {err:#}"),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::api_types::HomeAutomationPostBackData;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::Router;
+use base64::engine::Engine;
+use serde::Deserialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::sync::mpsc::Sender;
+
+/// A batch of home automation events that the hub posted back to us
+#[derive(Debug)]
+pub struct PostbackEvent {
+    /// The serial number of the hub, taken from the postback url
+    pub serial: String,
+    pub data: Vec<HomeAutomationPostBackData>,
+    /// Set when the hub advises that its configuration changed,
+    /// in which case `data` is empty
+    pub config_num: Option<i64>,
+}
+
+/// Returns the url that the hub should use to post back events
+/// to the server started by `start_postback_server`.
+/// `local_addr` is our own address, as seen by the hub.
+pub fn postback_url(local_addr: IpAddr, port: u16, serial: &str) -> String {
+    // SocketAddr takes care of bracketing IPv6 addresses
+    let addr = SocketAddr::new(local_addr, port);
+    format!("{addr}/pv-postback/{serial}")
+}
+
+fn generic<T: ToString + std::fmt::Display>(err: T) -> Response {
+    log::error!("err: {err:#}");
+    (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+}
+
+/// The hook data is sent with `Content-Type: application/x-www-form-urlencoded`
+/// but the data is most often actually base64 encoded json, so we just have
+/// to ignore the content type and extract from the data ourselves.
+async fn pv_postback<E: From<PostbackEvent> + Send + 'static>(
+    State(tx): State<Sender<E>>,
+    Path(serial): Path<String>,
+    body: String,
+) -> Result<Response, Response> {
+    #[derive(Deserialize, Debug)]
+    #[serde(rename_all = "camelCase")]
+    #[serde(deny_unknown_fields)]
+    pub struct ConfigUpdate {
+        pub config_num: i64,
+    }
+
+    if let Ok(decoded) = base64::engine::general_purpose::STANDARD.decode(&body) {
+        let data: Vec<HomeAutomationPostBackData> =
+            serde_json::from_slice(&decoded).map_err(generic)?;
+        log::debug!("postback: {data:?}");
+        tx.send(
+            PostbackEvent {
+                serial,
+                data,
+                config_num: None,
+            }
+            .into(),
+        )
+        .await
+        .map_err(generic)?;
+    } else if let Ok(config) = serde_urlencoded::from_str::<ConfigUpdate>(&body) {
+        log::warn!("** A shade failed post-move verification. New configuration {config:?}");
+        tx.send(
+            PostbackEvent {
+                serial,
+                data: vec![],
+                config_num: Some(config.config_num),
+            }
+            .into(),
+        )
+        .await
+        .map_err(generic)?;
+    } else {
+        log::error!("** Not sure what to do with {body}");
+    }
+    Ok((StatusCode::OK, "").into_response())
+}
+
+/// Start an http server on an ephemeral port to receive home automation
+/// postbacks from the hub. Events are sent to `tx`.
+/// Returns the port number of the server.
+pub async fn start_postback_server<E: From<PostbackEvent> + Send + 'static>(
+    tx: Sender<E>,
+) -> anyhow::Result<u16> {
+    start_postback_server_with_routes(tx, Router::new(), 0).await
+}
+
+/// Like `start_postback_server`, but additionally serves the
+/// routes from `extra`, and listens on `port` unless it is 0
+pub async fn start_postback_server_with_routes<E: From<PostbackEvent> + Send + 'static>(
+    tx: Sender<E>,
+    extra: Router,
+    port: u16,
+) -> anyhow::Result<u16> {
+    let app = Router::new()
+        .route("/pv-postback/:serial", post(pv_postback::<E>))
+        .with_state(tx)
+        .merge(extra);
+
+    // Prefer the dual-stack wildcard, so that we can receive
+    // postbacks from hubs using either address family, but
+    // fall back to v4 only on systems without v6 support
+    let listener = match tokio::net::TcpListener::bind((Ipv6Addr::UNSPECIFIED, port)).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::debug!("unable to listen on [::]:{port}: {err:#}, falling back to v4");
+            tokio::net::TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).await?
+        }
+    };
+    let addr = listener.local_addr()?;
+    log::info!("http server addr is {addr:?}");
+    tokio::spawn(async {
+        if let Err(err) = axum::serve(listener, app).await {
+            log::error!("http server stopped: {err:#}");
+        }
+    });
+    Ok(addr.port())
+}
@@ -0,0 +1,62 @@
+use serde::Deserialize;
+use std::path::Path;
+
+/// The contents of the file passed to `serve-mqtt --schedule-file`.
+///
+/// ```toml
+/// [[schedule]]
+/// cron = "0 7 * * *"
+/// shade = "Bedroom"
+/// percent = 0
+/// ```
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleFile {
+    #[serde(default)]
+    pub schedule: Vec<ScheduleEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleEntry {
+    /// A cron expression describing when to move the shade.
+    /// Both the traditional 5 field form and the 6 field form
+    /// that includes seconds are accepted.
+    pub cron: String,
+    /// The name or id of the shade to move
+    pub shade: String,
+    /// The position to move the shade to
+    pub percent: u8,
+}
+
+impl ScheduleFile {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("reading {}: {err:#}", path.display()))?;
+        let file: Self = toml::from_str(&text)
+            .map_err(|err| anyhow::anyhow!("parsing {}: {err:#}", path.display()))?;
+        for entry in &file.schedule {
+            if entry.percent > 100 {
+                anyhow::bail!(
+                    "{}: percent {} for shade {} must be in the range 0-100",
+                    path.display(),
+                    entry.percent,
+                    entry.shade
+                );
+            }
+        }
+        Ok(file)
+    }
+}
+
+impl ScheduleEntry {
+    /// Returns the cron expression in the 6 field form, including
+    /// seconds, that is required by the scheduler.
+    pub fn cron_with_seconds(&self) -> String {
+        if self.cron.split_whitespace().count() == 5 {
+            format!("0 {}", self.cron)
+        } else {
+            self.cron.clone()
+        }
+    }
+}
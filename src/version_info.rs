@@ -1,10 +1,32 @@
+use std::sync::OnceLock;
+
 const CI_TAG: &str = env!("PVIEW_CI_TAG");
 const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
+const BUILD_DATE: &str = env!("PVIEW_BUILD_DATE");
+const GIT_HASH: &str = env!("PVIEW_GIT_HASH");
 
-pub fn pview_version() -> &'static str {
+fn base_version() -> &'static str {
     if CI_TAG.is_empty() {
         PKG_VERSION
     } else {
         CI_TAG
     }
 }
+
+/// Returns the version, decorated with the git hash and build
+/// date when those are known, eg: `0.1.0+abc1234 (2025-01-15)`.
+pub fn pview_version() -> &'static str {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION.get_or_init(|| {
+        let mut version = base_version().to_string();
+        // The CI tag may already include the hash
+        if !GIT_HASH.is_empty() && !version.contains(GIT_HASH) {
+            version.push('+');
+            version.push_str(GIT_HASH);
+        }
+        if !BUILD_DATE.is_empty() {
+            version.push_str(&format!(" ({BUILD_DATE})"));
+        }
+        version
+    })
+}